@@ -0,0 +1,13 @@
+fn main() {
+    println!("cargo:rerun-if-changed=storage.x");
+
+    std::fs::write(
+        std::path::Path::new(std::env::var("OUT_DIR").unwrap().as_str()).join("storage.rs"),
+        fastxdr::Generator::default()
+            .with_no_std()
+            .with_module_attribute("#![allow(clippy::all)]")
+            .generate(include_str!("storage.x"))
+            .unwrap_or_else(|e| panic!("{}", e)),
+    )
+    .unwrap();
+}
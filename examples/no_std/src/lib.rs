@@ -0,0 +1,93 @@
+//! Decoding and encoding XDR types generated by `fastxdr` in a `#![no_std]`
+//! crate, using only `core` and `alloc`.
+
+#![no_std]
+
+extern crate alloc;
+
+include!(concat!(env!("OUT_DIR"), "/storage.rs"));
+
+use bytes::{Bytes, BytesMut};
+use core::convert::TryFrom;
+use xdr::{controller_status, drive_state, Encode, Error};
+
+/// Returns the number of failed drives in an encoded `controller_status`.
+pub fn failed_drives(buf: Bytes) -> Result<usize, Error> {
+    let status = controller_status::try_from(buf)?;
+
+    Ok(status
+        .drives
+        .iter()
+        .filter(|d| d.state == drive_state::DRIVE_FAILED)
+        .count())
+}
+
+/// Encodes `status` for sending to the host.
+pub fn encode_status<T>(status: &controller_status<T>) -> Result<Bytes, Error>
+where
+    T: AsRef<[u8]> + core::fmt::Debug,
+{
+    let mut buf = BytesMut::new();
+    status.encode(&mut buf)?;
+    Ok(buf.freeze())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+    use alloc::vec;
+    use xdr::{drive, firmware_hash, WireSize};
+
+    #[test]
+    fn test_round_trip() {
+        let status = controller_status {
+            firmware_id: Bytes::from_static(&[7; 16]),
+            drives: vec![
+                drive {
+                    slot: 0,
+                    serial: "S0".to_string(),
+                    state: drive_state::DRIVE_OK,
+                    capacity: 1 << 40,
+                },
+                drive {
+                    slot: 1,
+                    serial: "S1".to_string(),
+                    state: drive_state::DRIVE_FAILED,
+                    capacity: 1 << 40,
+                },
+            ],
+            spare: None,
+        };
+
+        let buf = encode_status(&status).unwrap();
+        assert_eq!(controller_status::try_from(buf.clone()).unwrap(), status);
+        assert_eq!(failed_drives(buf).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let buf = encode_status(&controller_status {
+            firmware_id: Bytes::from_static(&[7; 16]),
+            drives: vec![],
+            spare: None,
+        })
+        .unwrap();
+
+        assert_eq!(
+            failed_drives(buf.slice(..10)),
+            Err(Error::Incomplete { needed: 6 })
+        );
+    }
+
+    #[test]
+    fn test_fixed_opaque_wire_size() {
+        let hash = firmware_hash(Bytes::from_static(&[1; 32]));
+
+        let mut buf = BytesMut::new();
+        hash.encode(&mut buf).unwrap();
+        assert_eq!(hash.wire_size(), buf.len());
+        assert_eq!(firmware_hash::try_from(buf.freeze()).unwrap(), hash);
+    }
+}
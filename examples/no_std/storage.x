@@ -0,0 +1,24 @@
+/* The status reported by a storage controller. */
+
+const MAX_SERIAL = 32;
+
+typedef opaque firmware_hash[32];
+
+enum drive_state {
+	DRIVE_OK = 0,
+	DRIVE_DEGRADED = 1,
+	DRIVE_FAILED = 2
+};
+
+struct drive {
+	unsigned int slot;
+	string serial<MAX_SERIAL>;
+	drive_state state;
+	unsigned hyper capacity;
+};
+
+struct controller_status {
+	opaque firmware_id[16];
+	drive drives<>;
+	drive *spare;
+};
@@ -0,0 +1,15 @@
+fn main() {
+    println!("cargo:rerun-if-changed=calculator.x");
+
+    std::fs::write(
+        std::path::Path::new(std::env::var("OUT_DIR").unwrap().as_str()).join("calculator.rs"),
+        fastxdr::Generator::default()
+            .with_tokio_codec()
+            .with_rpc_client()
+            .with_rpc_server()
+            .with_module_attribute("#![allow(clippy::all)]")
+            .generate(include_str!("calculator.x"))
+            .unwrap_or_else(|e| panic!("{}", e)),
+    )
+    .unwrap();
+}
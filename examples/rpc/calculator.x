@@ -0,0 +1,35 @@
+/* A calculator service, called over ONC RPC. */
+
+const MAX_NAME = 32;
+
+enum operation {
+	OP_ADD = 0,
+	OP_SUB = 1,
+	OP_DIV = 2
+};
+
+struct calc_args {
+	operation op;
+	hyper lhs;
+	hyper rhs;
+};
+
+union calc_result switch (int status) {
+	case 0:
+		hyper value;
+	default:
+		void;
+};
+
+struct memory {
+	string name<MAX_NAME>;
+	opaque data<>;
+};
+
+program CALCULATOR {
+	version CALCULATOR_V1 {
+		void CALC_NULL(void) = 0;
+		calc_result CALC(calc_args) = 1;
+		memory RECALL(string) = 2;
+	} = 1;
+} = 0x20000099;
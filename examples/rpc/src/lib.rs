@@ -0,0 +1,273 @@
+//! Calling a service generated by `fastxdr` over ONC RPC, with the generated
+//! tokio codec framing the call and reply messages on a byte stream.
+
+include!(concat!(env!("OUT_DIR"), "/calculator.rs"));
+
+use fastxdr::bytes::{BufMut, Bytes};
+use futures_util::{SinkExt, StreamExt};
+use std::convert::TryFrom;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::Framed;
+use xdr::codec::{CodecError, XdrCodec};
+use xdr::{
+    calc_args, calc_result, client, memory, operation, server, CALCULATOR_V1Service, Encode, Error,
+};
+
+/// An undecoded ONC RPC message, read from or written to a single record.
+#[derive(Debug)]
+pub struct Message(pub Bytes);
+
+impl TryFrom<Bytes> for Message {
+    type Error = Error;
+
+    fn try_from(v: Bytes) -> Result<Self, Self::Error> {
+        Ok(Self(v))
+    }
+}
+
+impl Encode for Message {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+        buf.put_slice(&self.0);
+        Ok(())
+    }
+}
+
+/// A client [`client::Transport`] sending record-marked messages over a
+/// stream.
+#[derive(Debug)]
+pub struct StreamTransport<S> {
+    framed: Framed<S, XdrCodec<Message>>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> StreamTransport<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            framed: Framed::new(stream, XdrCodec::new()),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> client::Transport for StreamTransport<S> {
+    type Error = CodecError;
+
+    async fn send(&mut self, msg: Bytes) -> Result<(), CodecError> {
+        self.framed.send(Message(msg)).await
+    }
+
+    async fn recv(&mut self) -> Result<Bytes, CodecError> {
+        match self.framed.next().await {
+            Some(msg) => Ok(msg?.0),
+            None => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+        }
+    }
+}
+
+/// Replies to the calls read from `stream` until it is closed.
+pub async fn serve<S, D>(stream: S, d: D) -> Result<(), CodecError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    D: server::Dispatch,
+{
+    let mut framed = Framed::new(stream, XdrCodec::<Message>::new());
+    while let Some(msg) = framed.next().await {
+        let reply = server::handle(&d, msg?.0).await?;
+        framed.send(Message(reply)).await?;
+    }
+
+    Ok(())
+}
+
+/// The calculator service, returning a `calc_result` with a status of 1 for
+/// calculations that overflow or divide by zero.
+#[derive(Debug, Default)]
+pub struct Calculator;
+
+impl CALCULATOR_V1Service for Calculator {
+    async fn calc_null(&self) {}
+
+    async fn calc(&self, v: calc_args) -> calc_result {
+        let value = match v.op {
+            operation::OP_ADD => v.lhs.checked_add(v.rhs),
+            operation::OP_SUB => v.lhs.checked_sub(v.rhs),
+            operation::OP_DIV => v.lhs.checked_div(v.rhs),
+        };
+
+        match value {
+            Some(v) => calc_result::v_0(v),
+            None => calc_result::default(1),
+        }
+    }
+
+    async fn recall(&self, name: String) -> memory<Bytes> {
+        let data = Bytes::copy_from_slice(name.as_bytes());
+        memory { name, data }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fastxdr::bytes::BytesMut;
+    use xdr::client::ClientError;
+    use xdr::{
+        accepted_reply, accepted_reply_data, auth_flavor, opaque_auth, reply_body, rpc_msg,
+        rpc_msg_body, CALCULATOR_V1Call, CALCULATOR_V1Client, CALCULATOR_V1Server, WireSize,
+    };
+
+    #[tokio::test]
+    async fn test_codec_round_trip() {
+        let (a, b) = tokio::io::duplex(64);
+        let mut tx = Framed::new(a, XdrCodec::<memory<Bytes>>::new());
+        let mut rx = Framed::new(b, XdrCodec::<memory<Bytes>>::new());
+
+        // The opaque data is not a multiple of 4 bytes, and must be padded.
+        let m = || memory {
+            name: "answer".to_string(),
+            data: Bytes::from_static(&[4, 2, 0, 1, 9]),
+        };
+        tx.send(m()).await.unwrap();
+        tx.send(m()).await.unwrap();
+
+        let mut buf = BytesMut::new();
+        m().encode(&mut buf).unwrap();
+        assert_eq!(m().wire_size(), buf.len());
+
+        assert_eq!(rx.next().await.unwrap().unwrap(), m());
+        assert_eq!(rx.next().await.unwrap().unwrap(), m());
+        assert!(rx.read_buffer().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_codec_max_record_len() {
+        let (a, b) = tokio::io::duplex(64);
+        let mut tx = Framed::new(a, XdrCodec::<memory<Bytes>>::new());
+        let mut rx = Framed::new(b, XdrCodec::<memory<Bytes>>::with_max_record_len(8));
+
+        let m = memory {
+            name: "answer".to_string(),
+            data: Bytes::new(),
+        };
+        tx.send(m).await.unwrap();
+
+        assert!(matches!(
+            rx.next().await.unwrap(),
+            Err(CodecError::Xdr(Error::InvalidLength))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_client() {
+        let (a, b) = tokio::io::duplex(1024);
+
+        // Reply to a single CALC call, as the server would.
+        let server = tokio::spawn(async move {
+            let mut framed = Framed::new(b, XdrCodec::<Message>::new());
+            let mut msg = framed.next().await.unwrap().unwrap().0;
+
+            let call = rpc_msg::try_from(&mut msg).unwrap();
+            let body = match call.body {
+                rpc_msg_body::CALL(b) => b,
+                b => panic!("unexpected message body {:?}", b),
+            };
+            assert_eq!((body.prog, body.vers, body.proc), (0x20000099, 1, 1));
+            assert_eq!(
+                CALCULATOR_V1Call::decode(body.proc, msg).unwrap(),
+                CALCULATOR_V1Call::CALC(calc_args {
+                    op: operation::OP_ADD,
+                    lhs: 40,
+                    rhs: 2,
+                })
+            );
+
+            // The client discards replies to other calls.
+            for (xid, value) in [(call.xid.wrapping_add(1), 13), (call.xid, 42)] {
+                let mut buf = BytesMut::new();
+                rpc_msg {
+                    xid,
+                    body: rpc_msg_body::REPLY(reply_body::MSG_ACCEPTED(accepted_reply {
+                        verf: opaque_auth {
+                            flavor: auth_flavor::AUTH_NONE,
+                            body: Bytes::new(),
+                        },
+                        reply_data: accepted_reply_data::SUCCESS,
+                    })),
+                }
+                .encode(&mut buf)
+                .unwrap();
+                calc_result::v_0(value).encode(&mut buf).unwrap();
+
+                framed.send(Message(buf.freeze())).await.unwrap();
+            }
+        });
+
+        let mut client = CALCULATOR_V1Client::new(StreamTransport::new(a));
+        let got = client
+            .calc(calc_args {
+                op: operation::OP_ADD,
+                lhs: 40,
+                rhs: 2,
+            })
+            .await
+            .unwrap();
+        assert_eq!(got, calc_result::v_0(42));
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_loopback() {
+        let (a, b) = tokio::io::duplex(1024);
+        let server = tokio::spawn(serve(b, CALCULATOR_V1Server(Calculator)));
+
+        let mut client = CALCULATOR_V1Client::new(StreamTransport::new(a));
+        client.calc_null().await.unwrap();
+
+        let got = client
+            .calc(calc_args {
+                op: operation::OP_SUB,
+                lhs: 2,
+                rhs: 44,
+            })
+            .await
+            .unwrap();
+        assert_eq!(got, calc_result::v_0(-42));
+
+        let got = client
+            .calc(calc_args {
+                op: operation::OP_DIV,
+                lhs: 42,
+                rhs: 0,
+            })
+            .await
+            .unwrap();
+        assert_eq!(got, calc_result::default(1));
+
+        let got = client.recall("bananas".to_string()).await.unwrap();
+        assert_eq!(got.name, "bananas");
+        assert_eq!(got.data, Bytes::from_static(b"bananas"));
+
+        // Closing the client stream stops the server.
+        drop(client);
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_loopback_rejected_calls() {
+        let (a, b) = tokio::io::duplex(1024);
+        tokio::spawn(serve(b, CALCULATOR_V1Server(Calculator)));
+
+        let mut client = CALCULATOR_V1Client::new(StreamTransport::new(a));
+        let rpc = client.rpc_mut();
+
+        let got = rpc.call(42, &CALCULATOR_V1Call::CALC_NULL).await;
+        assert!(matches!(got, Err(ClientError::ProcUnavail)));
+
+        // CALC called without its arguments.
+        let got = rpc.call(1, &CALCULATOR_V1Call::CALC_NULL).await;
+        assert!(matches!(got, Err(ClientError::GarbageArgs)));
+
+        // The server is still serving calls after rejecting them.
+        client.calc_null().await.unwrap();
+    }
+}
@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ArraySize {
     Known(u32),
@@ -9,10 +11,11 @@ where
     T: AsRef<str>,
 {
     fn from(v: T) -> Self {
-        v.as_ref()
-            .parse::<u32>()
+        crate::ast::Literal::parse(v.as_ref())
+            .ok()
+            .and_then(|v| u32::try_from(v.value()).ok())
             .map(Self::Known)
-            .unwrap_or_else(|_| Self::Constant(v.as_ref().to_string()))
+            .unwrap_or_else(|| Self::Constant(v.as_ref().to_string()))
     }
 }
 
@@ -47,6 +50,14 @@ where
         }
     }
 
+    pub fn unwrap_array_mut(&mut self) -> &mut T {
+        match self {
+            Self::None(t) => t,
+            Self::FixedSize(t, _) => t,
+            Self::VariableSize(t, _) => t,
+        }
+    }
+
     pub fn write_with_bounds<S, W>(&self, f: &mut W, b: Option<&[S]>) -> std::fmt::Result
     where
         S: AsRef<str>,
@@ -63,7 +74,7 @@ where
                         .join(", ")
                 )
             })
-            .unwrap_or_else(|| "".to_string());
+            .unwrap_or_default();
 
         match self {
             Self::None(t) => write!(f, "{}{}", t, bounds),
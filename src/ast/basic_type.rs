@@ -6,13 +6,16 @@ pub enum BasicType {
     I64,
     F32,
     F64,
+    /// A quadruple-precision float, represented by the `Quadruple` wrapper
+    /// around its 16 byte encoding.
+    Quadruple,
     String,
     Bool,
     Opaque,
     Ident(String),
 }
 
-impl<'a> BasicType {
+impl BasicType {
     pub fn as_str(&self) -> &str {
         match self {
             Self::U32 => "u32",
@@ -21,6 +24,7 @@ impl<'a> BasicType {
             Self::I64 => "i64",
             Self::F32 => "f32",
             Self::F64 => "f64",
+            Self::Quadruple => "Quadruple",
             Self::Bool => "bool",
             Self::String => "String",
             Self::Opaque => "T",
@@ -56,42 +60,39 @@ impl<'a> BasicType {
     }
 }
 
-impl<'a> std::fmt::Display for BasicType {
+impl std::fmt::Display for BasicType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_safe_string())
     }
 }
 
+/// Maps XDR and C type names to a `BasicType`.
+///
+/// XDR encodes all integers narrower than 32 bits in 4 bytes, so the C `char`,
+/// `short` and `long` types are mapped to 32 bit integers.
 impl<'a> From<&'a str> for BasicType {
     fn from(v: &'a str) -> Self {
-        match v.trim() {
-            "unsigned int" | "uint32_t" | "u32" | "unsigned" => Self::U32,
-            "int" | "int32_t" | "i32" => Self::I32,
+        // Normalise the whitespace between the words of unsigned types.
+        let v = v.split_whitespace().collect::<Vec<_>>().join(" ");
+        match v.as_str() {
+            "unsigned int" | "uint32_t" | "u32" | "unsigned" | "unsigned char"
+            | "unsigned short" | "unsigned long" => Self::U32,
+            "int" | "int32_t" | "i32" | "char" | "short" | "long" => Self::I32,
             "unsigned hyper" | "uint64_t" | "u64" => Self::U64,
             "hyper" | "int64_t" | "i64" => Self::I64,
             "float" => Self::F32,
             "double" => Self::F64,
+            "quadruple" => Self::Quadruple,
             "string" => Self::String,
             "opaque" => Self::Opaque,
             "bool" => Self::Bool,
-            s => Self::Ident(s.to_string()),
+            _ => Self::Ident(v),
         }
     }
 }
 
-impl<'a> From<String> for BasicType {
+impl From<String> for BasicType {
     fn from(v: String) -> Self {
-        match v.trim() {
-            "unsigned int" | "uint32_t" | "u32" => Self::U32,
-            "int" | "int32_t" | "i32" => Self::I32,
-            "unsigned hyper" | "uint64_t" | "u64" => Self::U64,
-            "hyper" | "int64_t" | "i64" => Self::I64,
-            "float" => Self::F32,
-            "double" => Self::F64,
-            "string" => Self::String,
-            "opaque" => Self::Opaque,
-            "bool" => Self::Bool,
-            s => Self::Ident(s.to_string()),
-        }
+        Self::from(v.as_str())
     }
 }
@@ -1,4 +1,5 @@
 use super::*;
+use std::convert::TryFrom;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Enum {
@@ -7,18 +8,18 @@ pub struct Enum {
 }
 
 impl<'a> Enum {
-    pub(crate) fn new(vs: Vec<Node<'a>>) -> Self {
-        let name = vs[0].ident_str().to_string();
+    pub(crate) fn new(vs: Vec<Node<'a>>) -> Result<Self> {
+        let name = ident_at(&vs, 0)?.to_string();
 
         let mut vars = Vec::new();
         for v in vs.into_iter().skip(1) {
-            vars.push(Variant::new(v))
+            vars.push(Variant::new(v)?)
         }
 
-        Self {
+        Ok(Self {
             name,
             variants: vars,
-        }
+        })
     }
 }
 
@@ -43,38 +44,36 @@ impl std::fmt::Display for VariantValue {
     }
 }
 
-impl<T> From<T> for VariantValue
-where
-    T: AsRef<str>,
-{
-    fn from(v: T) -> Self {
-        let v = v.as_ref();
-        if v.starts_with("0x") {
-            let clean = v.trim_start_matches("0x");
-            return Self::Numeric(i32::from_str_radix(clean, 16).unwrap());
+impl TryFrom<&str> for VariantValue {
+    type Error = crate::Error;
+
+    fn try_from(v: &str) -> Result<Self> {
+        if !v.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+            return Ok(Self::String(v.to_string()));
         }
 
-        v.parse::<i32>()
+        let value = Literal::parse(v)?.value();
+        i32::try_from(value)
             .map(Self::Numeric)
-            .unwrap_or_else(|_| Self::String(v.to_string()))
+            .map_err(|_| format!("enum value {} does not fit in an i32", v).into())
     }
 }
 
 impl<'a> Variant {
-    fn new(v: Node<'a>) -> Self {
+    fn new(v: Node<'a>) -> Result<Self> {
         let f = match v {
             Node::EnumVariant(f) => f,
-            e => panic!("not a struct field: {:?}", e),
+            e => return Err(format!("not an enum variant: {:?}", e).into()),
         };
 
         if f.len() != 2 {
-            panic!("unexpected number of tokens in enum")
+            return Err("unexpected number of tokens in enum".into());
         }
 
-        Self {
-            name: f[0].ident_str().to_string(),
-            value: f[1].ident_str().into(),
-        }
+        Ok(Self {
+            name: f[0].ident_str()?.to_string(),
+            value: VariantValue::try_from(f[1].ident_str()?)?,
+        })
     }
 }
 
@@ -89,7 +88,7 @@ mod tests {
                 .next()
                 .unwrap();
 
-            let root = walk(ast);
+            let root = walk(ast).unwrap();
             let union = root.into_inner().remove(0);
             match union {
                 Node::Enum(u) => u,
@@ -178,4 +177,48 @@ mod tests {
             VariantValue::String("CONST_THREE".to_string())
         );
     }
+
+    #[test]
+    fn test_enum_hex_overflow() {
+        let input = r#"
+        enum flags {
+                ALL = 0xFFFFFFFFF
+        };"#;
+
+        match Ast::new(input).unwrap_err() {
+            crate::Error::InvalidDefinition { message, span, .. } => {
+                assert_eq!(message, "enum value 0xFFFFFFFFF does not fit in an i32");
+                assert_eq!(span.line, 2);
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_enum_literals() {
+        let got = parse!(
+            r#"
+        enum values {
+                NEGATIVE    = -1,
+                HEX         = 0x10,
+                NEGATIVE_HEX = -0x10,
+                OCTAL       = 010
+        };"#
+        );
+
+        let values = got
+            .variants
+            .iter()
+            .map(|v| v.value.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                VariantValue::Numeric(-1),
+                VariantValue::Numeric(16),
+                VariantValue::Numeric(-16),
+                VariantValue::Numeric(8),
+            ]
+        );
+    }
 }
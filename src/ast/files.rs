@@ -0,0 +1,284 @@
+use super::*;
+use crate::error::find_ident;
+use crate::Error;
+use std::collections::BTreeSet;
+
+/// An XDR spec parsed into the [`Ast`], and the file it was read from.
+#[derive(Debug)]
+pub(crate) struct Source {
+    /// The file name reported in errors, or `None` for a spec passed directly
+    /// as a string.
+    pub(crate) name: Option<String>,
+    pub(crate) text: String,
+}
+
+impl Source {
+    pub(crate) fn new(name: Option<&str>, text: &str) -> Self {
+        Source {
+            name: name.map(ToString::to_string),
+            text: text.to_string(),
+        }
+    }
+
+    /// Attributes `e` to this source if it was read from a named file.
+    pub(crate) fn error(&self, e: Error) -> Error {
+        match self.name {
+            Some(ref name) => e.in_file(name.as_str()),
+            None => e,
+        }
+    }
+}
+
+/// The location of a top-level definition in the XDR sources.
+#[derive(Debug, Clone)]
+pub(crate) struct Definition {
+    /// The index of the source the definition is read from.
+    pub(crate) file: usize,
+
+    /// The byte range of the definition within its source.
+    pub(crate) range: (usize, usize),
+}
+
+impl Ast {
+    /// Returns the names of the spec files, in the order they were parsed.
+    ///
+    /// Specs passed directly as a string have no name.
+    pub fn files(&self) -> impl std::iter::Iterator<Item = Option<&str>> {
+        self.sources.iter().map(|s| s.name.as_deref())
+    }
+
+    /// Returns the name of the file defining the type or program `name`.
+    pub fn file_of<T: AsRef<str>>(&self, name: T) -> Option<&str> {
+        self.definitions
+            .get(name.as_ref())
+            .and_then(|d| self.sources[d.file].name.as_deref())
+    }
+
+    /// Restricts the generated code to the definitions read from the source
+    /// `file`, or all definitions if `None`.
+    ///
+    /// The definitions in the other sources can still be referenced.
+    pub(crate) fn set_emitted(&mut self, file: Option<usize>) {
+        self.emitted = file;
+    }
+
+    /// Iterates over the types code is generated for.
+    pub(crate) fn emitted_types(&self) -> impl std::iter::Iterator<Item = &AstType> {
+        self.types()
+            .0
+            .iter()
+            .filter(move |(name, _)| self.emits(name))
+            .map(|(_, t)| t)
+    }
+
+    /// Iterates over the programs code is generated for.
+    pub(crate) fn emitted_programs(&self) -> impl std::iter::Iterator<Item = &Program> {
+        self.programs().iter().filter(move |p| self.emits(p.name()))
+    }
+
+    /// Iterates over the constants code is generated for.
+    pub(crate) fn emitted_constants(
+        &self,
+    ) -> impl std::iter::Iterator<Item = (&String, &ConstantType)> {
+        self.constants()
+            .iter()
+            .filter(move |(name, _)| self.emits_constant(name))
+    }
+
+    /// Returns true if code is generated for the type or program `name`.
+    pub(crate) fn emits(&self, name: &str) -> bool {
+        match self.emitted {
+            Some(file) => self.definitions.get(name).map(|d| d.file) == Some(file),
+            None => true,
+        }
+    }
+
+    /// Returns true if code is generated for the constant `name`.
+    pub(crate) fn emits_constant(&self, name: &str) -> bool {
+        match self.emitted {
+            Some(file) => self.constant_files.get(name) == Some(&file),
+            None => true,
+        }
+    }
+
+    /// Returns the indexes of the sources defining the types and constants
+    /// referenced by the definitions in the source `file`, excluding `file`
+    /// itself.
+    ///
+    /// References to typedefs also include the typedef targets, as the
+    /// generated code may name the target type directly.
+    pub(crate) fn dependencies(&self, file: usize) -> BTreeSet<usize> {
+        let mut pending = Vec::new();
+        for (name, t) in self.types().0.iter() {
+            if self.definitions.get(name).map(|d| d.file) == Some(file) {
+                pending.extend(references(t));
+            }
+        }
+        for program in self.programs().iter() {
+            if self.definitions.get(program.name()).map(|d| d.file) == Some(file) {
+                pending.extend(program_references(program));
+            }
+        }
+
+        let mut seen = BTreeSet::new();
+        let mut deps = BTreeSet::new();
+        while let Some(name) = pending.pop() {
+            if !seen.insert(name) {
+                continue;
+            }
+
+            if let Some(d) = self.definitions.get(name) {
+                deps.insert(d.file);
+                if let Some(t @ AstType::Typedef(_)) = self.types().get(name) {
+                    pending.extend(references(t));
+                }
+            } else if let Some(&f) = self.constant_files.get(name) {
+                deps.insert(f);
+            }
+        }
+
+        deps.remove(&file);
+        deps
+    }
+
+    /// Returns the first source containing the identifier `name`, and the
+    /// offset of its first occurrence in it.
+    pub(crate) fn locate(&self, name: &str) -> (&Source, usize) {
+        let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let source = self
+            .sources
+            .iter()
+            .find(|s| {
+                s.text
+                    .split(|c: char| !is_ident(c))
+                    .any(|ident| ident == name)
+            })
+            .unwrap_or(&self.sources[0]);
+
+        (source, find_ident(&source.text, 0, source.text.len(), name))
+    }
+}
+
+/// Returns the names of the types and constants referenced by `t`.
+fn references(t: &AstType) -> Vec<&str> {
+    fn array<'a>(refs: &mut Vec<&'a str>, t: &'a ArrayType<BasicType>) {
+        if let BasicType::Ident(name) = t.unwrap_array() {
+            refs.push(name.as_str());
+        }
+        match t {
+            ArrayType::FixedSize(_, ArraySize::Constant(c))
+            | ArrayType::VariableSize(_, Some(ArraySize::Constant(c))) => refs.push(c.as_str()),
+            _ => {}
+        }
+    }
+
+    let mut refs = Vec::new();
+    match t {
+        AstType::Struct(s) => {
+            for f in s.fields.iter() {
+                array(&mut refs, &f.field_value);
+            }
+        }
+        AstType::Union(u) => {
+            if let BasicType::Ident(name) = &u.switch.var_type {
+                refs.push(name.as_str());
+            }
+            for c in u.cases.iter().chain(u.default.iter()) {
+                array(&mut refs, &c.field_value);
+            }
+            refs.extend(
+                u.cases
+                    .iter()
+                    .flat_map(|c| c.case_values.iter())
+                    .chain(u.void_cases.iter())
+                    .map(|v| v.as_str()),
+            );
+        }
+        AstType::Enum(e) => {
+            for v in e.variants.iter() {
+                if let VariantValue::String(c) = &v.value {
+                    refs.push(c.as_str());
+                }
+            }
+        }
+        AstType::Typedef(t) => {
+            if let BasicType::Ident(name) = &t.target {
+                refs.push(name.as_str());
+            }
+            array(&mut refs, &t.alias);
+        }
+    }
+
+    refs
+}
+
+/// Returns the names of the types and constants referenced by `program`.
+fn program_references(program: &Program) -> Vec<&str> {
+    let mut refs = vec![program.number.as_str()];
+    for version in program.versions.iter() {
+        refs.push(version.number.as_str());
+        for p in version.procedures.iter() {
+            refs.push(p.number.as_str());
+            refs.extend(
+                p.args
+                    .iter()
+                    .chain(p.result.iter())
+                    .filter_map(|t| match t {
+                        BasicType::Ident(name) => Some(name.as_str()),
+                        _ => None,
+                    }),
+            );
+        }
+    }
+
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependencies() {
+        let files = [
+            ("inner.x", "const SIZE = 4; struct inner { int x; };"),
+            ("alias.x", "typedef inner alias;"),
+            ("outer.x", "struct outer { alias a; opaque b[SIZE]; };"),
+        ];
+
+        let ast = Ast::from_files(&files).unwrap();
+        assert_eq!(ast.file_of("alias"), Some("alias.x"));
+        assert_eq!(ast.file_of("missing"), None);
+
+        // The typedef target is a dependency of the files referencing the
+        // typedef.
+        assert_eq!(ast.dependencies(2), vec![0, 1].into_iter().collect());
+        assert_eq!(ast.dependencies(1), vec![0].into_iter().collect());
+        assert!(ast.dependencies(0).is_empty());
+    }
+
+    #[test]
+    fn test_emitted() {
+        let files = [
+            ("a.x", "const A = 1; struct a { int x; };"),
+            ("b.x", "const B = 2; struct b { a x; };"),
+        ];
+
+        let mut ast = Ast::from_files(&files).unwrap();
+        assert_eq!(ast.emitted_types().count(), 2);
+
+        ast.set_emitted(Some(1));
+        let names = ast
+            .emitted_types()
+            .map(|t| match t {
+                AstType::Struct(s) => s.name(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["b"]);
+        assert_eq!(
+            ast.emitted_constants().map(|(k, _)| k).collect::<Vec<_>>(),
+            vec!["B"]
+        );
+    }
+}
@@ -1,9 +1,11 @@
-use crate::ast::Node;
+use crate::ast::{ident_at, Literal, Node};
+use crate::Result;
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
 
 #[derive(Debug)]
 pub enum ConstantType {
-    ConstValue(String),
+    ConstValue(Literal),
     EnumValue { enum_name: String, variant: String },
 }
 
@@ -21,21 +23,22 @@ pub struct ConstantIndex(pub BTreeMap<String, ConstantType>);
 
 impl ConstantIndex {
     /// Build an index of all consts / enums for use in the union switches.
-    pub(crate) fn new<'a>(ast: &'a Node<'a>) -> ConstantIndex {
+    pub(crate) fn new<'a>(ast: &'a Node<'a>) -> Result<ConstantIndex> {
         let mut case_values = BTreeMap::new();
         if let Node::Root(r) = ast {
             for item in r.iter() {
                 match item {
                     Node::Constant(vs) => {
                         // Map constants to themselves, they do not require namespacing.
+                        let name = ident_at(vs, 0)?;
                         if case_values
                             .insert(
-                                vs[0].ident_str().to_string(),
-                                ConstantType::ConstValue(vs[1].ident_str().to_string()),
+                                name.to_string(),
+                                ConstantType::ConstValue(Literal::parse(ident_at(vs, 1)?)?),
                             )
                             .is_some()
                         {
-                            panic!("duplicate case keys {}", vs[0].ident_str());
+                            return Err(format!("duplicate case keys {}", name).into());
                         }
                     }
                     Node::Enum(e) => {
@@ -54,7 +57,9 @@ impl ConstantIndex {
                                 )
                                 .is_some()
                             {
-                                panic!("duplicate case keys {}", v.name.as_str());
+                                return Err(
+                                    format!("duplicate case keys {}", v.name.as_str()).into()
+                                );
                             }
                         }
                     }
@@ -63,7 +68,7 @@ impl ConstantIndex {
             }
         }
 
-        ConstantIndex(case_values)
+        Ok(ConstantIndex(case_values))
     }
 
     /// Returns the constant value as a string for `name`.
@@ -71,6 +76,15 @@ impl ConstantIndex {
         self.0.get(name.as_ref())
     }
 
+    /// Returns the value of the constant `name` as an array size.
+    pub(crate) fn size(&self, name: &str) -> Result<u32> {
+        match self.get(name) {
+            Some(ConstantType::ConstValue(v)) => u32::try_from(v.value())
+                .map_err(|_| format!("invalid array size {} = {}", name, v).into()),
+            _ => Err(format!("unknown constant {}", name).into()),
+        }
+    }
+
     /// Iterates over the types in the constant index.
     pub fn iter(&self) -> impl std::iter::Iterator<Item = (&String, &ConstantType)> {
         self.0.iter()
@@ -5,14 +5,17 @@ use std::collections::HashSet;
 pub struct GenericIndex(pub HashSet<String>);
 
 impl GenericIndex {
-    pub(crate) fn new<'a>(ast: &'a Node<'a>) -> GenericIndex {
+    /// Indexes the types containing opaque data, and if `borrowed` is true,
+    /// strings - the types that borrow from the buffer when decoding from a
+    /// slice.
+    pub(crate) fn new<'a>(ast: &'a Node<'a>, borrowed: bool) -> GenericIndex {
         // Define a recursive ast walker that visits all values in the tree, looking
         // for "opaque" types.
         //
         // Types containing opaque types, and types containing those types (and so
         // on) are added to index to build a full set of type names that require
         // generic bounds.
-        fn recurse<'a>(v: &'a Node<'a>, index: &mut HashSet<String>) -> bool {
+        fn recurse<'a>(v: &'a Node<'a>, index: &mut HashSet<String>, borrowed: bool) -> bool {
             // Get the type name to see if it is already marked.
             //
             // Only structs, unions and typedefs can contain sub-types that may be
@@ -33,54 +36,54 @@ impl GenericIndex {
 
             // Otherwise recurse into children looking for an "opaque" data type
             let contains_opaque = match v {
-                Node::Type(v) => match v {
-                    BasicType::Opaque => true,
-                    BasicType::Ident(i) => index.contains(i.as_str()),
-                    _ => false,
-                },
+                Node::Type(v) => is_generic(v, index, borrowed),
 
                 // These Nodes can contain inner opaque types, or contain compound
                 // types that themselves contain opaques.
-                Node::Struct(v) => v.inner_types().iter().any(|t| match t.unwrap_array() {
-                    BasicType::Opaque => true,
-                    BasicType::Ident(i) => index.contains(i.as_str()),
-                    _ => false,
-                }),
-                Node::Union(v) => v.inner_types().iter().any(|t| match t.unwrap_array() {
-                    BasicType::Opaque => true,
-                    BasicType::Ident(i) => index.contains(i.as_str()),
-                    _ => false,
-                }),
+                Node::Struct(v) => v
+                    .inner_types()
+                    .iter()
+                    .any(|t| is_generic(t.unwrap_array(), index, borrowed)),
+                Node::Union(v) => v
+                    .inner_types()
+                    .iter()
+                    .any(|t| is_generic(t.unwrap_array(), index, borrowed)),
 
-                Node::Typedef(v) => match v.target {
-                    BasicType::Opaque => true,
-                    _ => index.contains(v.target.as_str()),
-                },
+                Node::Typedef(v) => is_generic(&v.target, index, borrowed),
 
                 Node::Root(v) => v.iter().fold(false, |mut acc, v| {
-                    if recurse(v, index) {
+                    if recurse(v, index, borrowed) {
                         acc = true;
                     }
                     acc
                 }),
 
                 // These Nodes will never contain an opaque/generic type.
-                Node::EOF
+                Node::Eof
                 | Node::Enum(_)
+                | Node::Program(_)
                 | Node::Constant(_)
                 | Node::EnumVariant(_)
                 | Node::ArrayVariable(_)
                 | Node::ArrayFixed(_) => false,
 
-                // These nodes are not reachable in the tree
-                Node::Ident(_)
-                | Node::UnionDefault(_)
+                // These nodes are consumed when constructing the compound
+                // types above, or flattened into the root, and never appear
+                // in the tree.
+                Node::UnionDefault(_)
+                | Node::InlineStruct(_)
+                | Node::InlineUnion(_)
+                | Node::InlineEnum(_)
+                | Node::Definitions(_)
                 | Node::UnionCase(_)
                 | Node::Option(_)
                 | Node::UnionDataField(_)
                 | Node::UnionVoid
                 | Node::StructDataField(_)
-                | Node::Array(_) => unreachable!("{:?}", &v),
+                | Node::ProgramVersion(_)
+                | Node::Procedure(_)
+                | Node::ProcedureVoid
+                | Node::Array(_) => false,
             };
 
             // If there was a type name, and it contains an opaque type, add it to
@@ -99,7 +102,7 @@ impl GenericIndex {
         let mut last_size: isize = -1;
         while last_size != index.len() as isize {
             last_size = index.len() as isize;
-            recurse(ast, &mut index);
+            recurse(ast, &mut index, borrowed);
         }
 
         GenericIndex(index)
@@ -110,6 +113,16 @@ impl GenericIndex {
     }
 }
 
+/// Returns true if a value of type `t` makes the type containing it generic.
+fn is_generic(t: &BasicType, index: &HashSet<String>, borrowed: bool) -> bool {
+    match t {
+        BasicType::Opaque => true,
+        BasicType::String => borrowed,
+        BasicType::Ident(i) => index.contains(i.as_str()),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -254,4 +267,40 @@ union READ4res switch (nfsstat4 status) {
 
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn test_generic_pushup_borrowed_strings() {
+        let input = r#"
+typedef string  name4<>;
+struct entry {
+        name4        name;
+};
+struct listing {
+        entry        entries<>;
+        string       comment<>;
+};
+struct counts {
+        uint32_t     total;
+};
+        "#;
+
+        let ast = crate::ast::Ast::parse(input, &Default::default(), true).unwrap();
+        let got = ast.generics();
+
+        let mut want = HashSet::new();
+        want.insert("name4");
+        want.insert("entry");
+        want.insert("listing");
+
+        let mut got: Vec<String> = got.0.iter().cloned().collect();
+        let mut want: Vec<&str> = want.iter().cloned().collect();
+
+        let got = got.as_mut_slice();
+        let want = want.as_mut_slice();
+
+        got.sort_unstable();
+        want.sort_unstable();
+
+        assert_eq!(got, want);
+    }
 }
@@ -0,0 +1,26 @@
+use std::collections::BTreeMap;
+
+/// An index of the XDR types replaced by external Rust types, keyed by XDR
+/// type name.
+#[derive(Debug, Default)]
+pub struct MappingIndex(pub BTreeMap<String, String>);
+
+impl MappingIndex {
+    /// Returns the path of the Rust type replacing the XDR type `name`.
+    pub fn get<T: AsRef<str>>(&self, name: T) -> Option<&str> {
+        self.0.get(name.as_ref()).map(|v| v.as_str())
+    }
+
+    /// Returns the name of the XDR type replaced by the Rust type `path`.
+    pub fn source<T: AsRef<str>>(&self, path: T) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(_, p)| p.as_str() == path.as_ref())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Iterates over the (XDR name, Rust path) pairs in the index.
+    pub fn iter(&self) -> impl std::iter::Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
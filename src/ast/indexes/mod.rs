@@ -6,3 +6,9 @@ pub use constants::*;
 
 mod type_index;
 pub use type_index::*;
+
+mod programs;
+pub use programs::*;
+
+mod mappings;
+pub use mappings::*;
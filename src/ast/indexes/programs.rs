@@ -0,0 +1,80 @@
+use crate::ast::{Node, Program};
+use std::collections::BTreeMap;
+
+#[derive(Debug)]
+pub struct ProgramIndex(pub BTreeMap<String, Program>);
+
+impl ProgramIndex {
+    /// Build an index of all ONC RPC program definitions, keyed by program
+    /// name.
+    pub(crate) fn new<'a>(ast: &'a Node<'a>) -> ProgramIndex {
+        let mut programs = BTreeMap::new();
+        if let Node::Root(r) = ast {
+            for item in r.iter() {
+                if let Node::Program(p) = item {
+                    programs.insert(p.name().to_string(), p.clone());
+                }
+            }
+        }
+
+        ProgramIndex(programs)
+    }
+
+    /// Returns the `Program` for `name`.
+    pub fn get<T: AsRef<str>>(&self, name: T) -> Option<&Program> {
+        self.0.get(name.as_ref())
+    }
+
+    /// Iterates over the programs in the index.
+    pub fn iter(&self) -> impl std::iter::Iterator<Item = &Program> {
+        self.0.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::BasicType;
+
+    #[test]
+    fn test_programs_in_index() {
+        let input = r#"
+            struct args {
+                uint32_t a;
+            };
+
+            program B_PROG {
+                version B_V1 {
+                    void B_NULL(void) = 0;
+                } = 1;
+            } = 0x20000002;
+
+            program A_PROG {
+                version A_V1 {
+                    void A_NULL(void) = 0;
+                    uint32_t A_CALL(args) = 1;
+                } = 1;
+            } = 0x20000001;
+        "#;
+
+        let ast = crate::ast::Ast::new(input).unwrap();
+        let got = ast.programs();
+
+        assert_eq!(got.0.len(), 2);
+        assert!(ast.types().get("args").is_some());
+
+        let mut iter = got.iter();
+        assert_eq!(iter.next().unwrap().name(), "A_PROG");
+        assert_eq!(iter.next().unwrap().name(), "B_PROG");
+
+        let prog = got.get("A_PROG").unwrap();
+        assert_eq!(prog.number, "0x20000001");
+        assert_eq!(prog.versions[0].procedures.len(), 2);
+        assert_eq!(
+            prog.versions[0].procedures[1].args,
+            vec![BasicType::Ident("args".to_string())]
+        );
+        assert_eq!(prog.versions[0].procedures[1].result, Some(BasicType::U32));
+
+        assert!(got.get("C_PROG").is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use super::*;
+
+/// Replaces the anonymous types declared inline in the fields of the type
+/// `name` with references to named types, appending their definitions to
+/// `defs`.
+///
+/// Each anonymous type is named after the type and field it is declared in,
+/// so the type of the `point` field in `struct shape` becomes `shape_point`.
+/// Anonymous types nested within them are hoisted recursively.
+pub(crate) fn hoist<'a>(
+    name: &str,
+    nodes: &mut [Node<'a>],
+    defs: &mut Vec<Node<'a>>,
+) -> Result<()> {
+    for node in nodes.iter_mut() {
+        match node {
+            Node::StructDataField(f) | Node::UnionDataField(f) => {
+                let field = match f.get(1) {
+                    Some(v) => v.ident_str()?.to_string(),
+                    None => return Err("missing field name".into()),
+                };
+                hoist_type(&format!("{}_{}", name, field), &mut f[0], defs)?;
+            }
+            Node::UnionCase(vs) | Node::UnionDefault(vs) => hoist(name, vs, defs)?,
+            _ => continue,
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces `node` with a reference to a type called `name` if it is an
+/// anonymous type, appending its definition to `defs`.
+///
+/// Returns true if `node` was an anonymous type.
+pub(crate) fn hoist_type<'a>(
+    name: &str,
+    node: &mut Node<'a>,
+    defs: &mut Vec<Node<'a>>,
+) -> Result<bool> {
+    if !matches!(
+        node,
+        Node::InlineStruct(_) | Node::InlineUnion(_) | Node::InlineEnum(_)
+    ) {
+        return Ok(false);
+    }
+
+    let ident = || Node::Type(BasicType::Ident(name.to_string()));
+    let def = match std::mem::replace(node, ident()) {
+        Node::InlineStruct(mut vs) => {
+            hoist(name, &mut vs, defs)?;
+            vs.insert(0, ident());
+            Node::Struct(Struct::new(vs)?)
+        }
+        Node::InlineUnion(mut vs) => {
+            hoist(name, &mut vs, defs)?;
+            vs.insert(0, ident());
+            Node::Union(Union::new(vs)?)
+        }
+        Node::InlineEnum(mut vs) => {
+            vs.insert(0, ident());
+            Node::Enum(Enum::new(vs)?)
+        }
+        v => return Err(format!("not an anonymous type: {:?}", v).into()),
+    };
+
+    defs.push(def);
+    Ok(true)
+}
+
+/// Returns `node`, followed by any types hoisted out of it.
+pub(crate) fn with_hoisted<'a>(node: Node<'a>, mut defs: Vec<Node<'a>>) -> Node<'a> {
+    if defs.is_empty() {
+        return node;
+    }
+
+    defs.insert(0, node);
+    Node::Definitions(defs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_struct_field() {
+        let ast = Ast::new(
+            r#"
+            struct shape {
+                struct {
+                    int x;
+                    int y;
+                } point;
+                unsigned int sides;
+            };"#,
+        )
+        .unwrap();
+
+        let shape = match ast.types().get("shape") {
+            Some(AstType::Struct(s)) => s,
+            v => panic!("unexpected type {:?}", v),
+        };
+        assert_eq!(
+            shape.fields[0].field_value,
+            ArrayType::None(BasicType::from("shape_point"))
+        );
+
+        let point = match ast.types().get("shape_point") {
+            Some(AstType::Struct(s)) => s,
+            v => panic!("unexpected type {:?}", v),
+        };
+        assert_eq!(point.fields.len(), 2);
+        assert_eq!(point.fields[0].field_name, "x");
+        assert_eq!(point.fields[1].field_name, "y");
+    }
+
+    #[test]
+    fn test_inline_nested() {
+        let ast = Ast::new(
+            r#"
+            struct outer {
+                struct {
+                    union switch (int kind) {
+                    case 1:
+                        enum { A = 1, B = 2 } e;
+                    default:
+                        void;
+                    } u;
+                } *next;
+            };"#,
+        )
+        .unwrap();
+
+        let names = ast
+            .types()
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["outer", "outer_next", "outer_next_u", "outer_next_u_e"]
+        );
+
+        let outer = match ast.types().get("outer") {
+            Some(AstType::Struct(s)) => s,
+            v => panic!("unexpected type {:?}", v),
+        };
+        assert!(outer.fields[0].is_optional);
+
+        assert_eq!(
+            ast.constants().get("A").unwrap().to_string(),
+            "outer_next_u_e::A"
+        );
+    }
+
+    #[test]
+    fn test_inline_typedef() {
+        let ast = Ast::new(
+            r#"
+            typedef struct {
+                int x;
+            } point;
+            typedef enum { RED = 0, GREEN = 1 } colour;
+            typedef struct {
+                int x;
+            } points<>;"#,
+        )
+        .unwrap();
+
+        assert!(matches!(ast.types().get("point"), Some(AstType::Struct(_))));
+        assert!(matches!(ast.types().get("colour"), Some(AstType::Enum(_))));
+        assert!(matches!(
+            ast.types().get("points_element"),
+            Some(AstType::Struct(_))
+        ));
+        assert_eq!(
+            ast.types().get("points"),
+            Some(&AstType::Typedef(Typedef {
+                target: BasicType::from("points_element"),
+                alias: ArrayType::VariableSize(BasicType::from("points"), None),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_inline_duplicate() {
+        let input = r#"
+            struct a_b {
+                int x;
+            };
+            struct a {
+                struct { int y; } b;
+            };"#;
+
+        match Ast::new(input).unwrap_err() {
+            crate::Error::DuplicateDefinition { name, span, .. } => {
+                assert_eq!(name, "a_b");
+                assert_eq!(span.line, 5);
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use super::BasicType;
+use crate::Result;
+
+/// An integer constant literal, written in decimal, hexadecimal (with a `0x`
+/// prefix) or octal (with a leading `0`) notation, and optionally negative.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Literal {
+    value: i128,
+
+    /// The literal as Rust source, in the notation used in the XDR spec.
+    rust: String,
+}
+
+impl Literal {
+    pub fn parse(v: &str) -> Result<Self> {
+        let (sign, digits) = match v.strip_prefix('-') {
+            Some(d) => ("-", d),
+            None => ("", v),
+        };
+
+        // Octal literals are also accepted with the Rust `0o` prefix, so
+        // literals survive being printed and parsed again.
+        let (radix, prefix, digits) = if let Some(d) = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            (16, "0x", d)
+        } else if let Some(d) = digits.strip_prefix("0o") {
+            (8, "0o", d)
+        } else if digits.len() > 1 && digits.starts_with('0') {
+            (8, "0o", &digits[1..])
+        } else {
+            (10, "", digits)
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return Err(format!("invalid integer constant {}", v).into());
+        }
+
+        let magnitude = u64::from_str_radix(digits, radix)
+            .map_err(|_| format!("integer constant {} is out of range", v))?;
+
+        let value = match sign {
+            "-" => -i128::from(magnitude),
+            _ => i128::from(magnitude),
+        };
+
+        if value < i128::from(i64::MIN) {
+            return Err(format!("integer constant {} is out of range", v).into());
+        }
+
+        Ok(Self {
+            value,
+            rust: format!("{}{}{}", sign, prefix, digits),
+        })
+    }
+
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+
+    /// Returns the smallest of `i32`, `u32`, `i64` and `u64` able to hold the
+    /// value, preferring the 32-bit types.
+    pub fn rust_type(&self) -> BasicType {
+        let v = self.value;
+        if v < 0 && v >= i128::from(i32::MIN) {
+            BasicType::I32
+        } else if v >= 0 && v <= i128::from(u32::MAX) {
+            BasicType::U32
+        } else if v <= i128::from(i64::MAX) {
+            BasicType::I64
+        } else {
+            BasicType::U64
+        }
+    }
+}
+
+impl std::fmt::Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rust)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_literal {
+        ($name: ident, $input: expr, $value: expr, $rust: expr, $type: expr) => {
+            #[test]
+            fn $name() {
+                let got = Literal::parse($input).unwrap();
+
+                assert_eq!(got.value(), $value);
+                assert_eq!(got.to_string(), $rust);
+                assert_eq!(got.rust_type(), $type);
+                assert_eq!(Literal::parse(&got.to_string()).unwrap(), got);
+            }
+        };
+    }
+
+    test_literal!(test_decimal, "42", 42, "42", BasicType::U32);
+    test_literal!(test_zero, "0", 0, "0", BasicType::U32);
+    test_literal!(test_negative, "-1", -1, "-1", BasicType::I32);
+    test_literal!(test_hex, "0x00000001", 1, "0x00000001", BasicType::U32);
+    test_literal!(test_hex_upper, "0XFF", 255, "0xFF", BasicType::U32);
+    test_literal!(test_negative_hex, "-0x10", -16, "-0x10", BasicType::I32);
+    test_literal!(test_octal, "0755", 0o755, "0o755", BasicType::U32);
+    test_literal!(
+        test_u32_max,
+        "0xFFFFFFFF",
+        0xFFFF_FFFF,
+        "0xFFFFFFFF",
+        BasicType::U32
+    );
+    test_literal!(
+        test_i64,
+        "4294967296",
+        4_294_967_296,
+        "4294967296",
+        BasicType::I64
+    );
+    test_literal!(
+        test_i64_negative,
+        "-2147483649",
+        -2_147_483_649,
+        "-2147483649",
+        BasicType::I64
+    );
+    test_literal!(
+        test_u64,
+        "0xffffffffffffffff",
+        0xFFFF_FFFF_FFFF_FFFF,
+        "0xffffffffffffffff",
+        BasicType::U64
+    );
+
+    #[test]
+    fn test_invalid() {
+        assert!(Literal::parse("09").is_err());
+        assert!(Literal::parse("0x").is_err());
+        assert!(Literal::parse("-").is_err());
+        assert!(Literal::parse("0x1ffffffffffffffff").is_err());
+        assert!(Literal::parse("-0xffffffffffffffff").is_err());
+    }
+}
@@ -16,9 +16,23 @@ use node::*;
 mod array;
 pub use array::*;
 
+mod literal;
+pub use literal::*;
+
 mod typedef;
 pub use typedef::*;
 
+mod inline;
+use inline::*;
+
+mod program;
+pub use program::*;
+
+mod validate;
+
+mod files;
+pub(crate) use files::*;
+
 pub mod indexes;
 use indexes::*;
 
@@ -27,10 +41,12 @@ pub trait CompoundType {
     fn contains_opaque(&self) -> bool;
 }
 
-use crate::Result;
+use crate::error::find_ident;
+use crate::{Error, Result};
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 
 #[derive(Parser)]
@@ -44,25 +60,109 @@ pub struct Ast {
     constant_index: ConstantIndex,
     generic_index: GenericIndex,
     type_index: TypeIndex,
+    program_index: ProgramIndex,
+    mapping_index: MappingIndex,
+
+    /// True if the generated types borrow from the buffer they are decoded
+    /// from.
+    borrowed: bool,
+
+    /// The XDR sources, used to locate types referenced in error messages.
+    sources: Vec<Source>,
+
+    /// The location of each type and program definition, keyed by name.
+    definitions: BTreeMap<String, Definition>,
+
+    /// The index of the source defining each constant and enum variant.
+    constant_files: BTreeMap<String, usize>,
+
+    /// The source whose definitions code is generated for, or `None` for all
+    /// of them.
+    emitted: Option<usize>,
 }
 
 impl Ast {
     pub fn new(xdr: &str) -> Result<Self> {
-        // Tokenise the input
-        let mut root = XDRParser::parse(Rule::item, xdr)?;
-        // Parse into an AST
-        let ast = walk(root.next().ok_or("unable to tokenise input")?);
+        Self::with_mappings(xdr, &BTreeMap::new())
+    }
+
+    /// Parses `xdr`, replacing references to the XDR types named in
+    /// `mappings` with the Rust type path each maps to.
+    ///
+    /// The mapped types are still parsed and indexed, as they describe the
+    /// wire representation of the Rust types replacing them.
+    pub fn with_mappings(xdr: &str, mappings: &BTreeMap<String, String>) -> Result<Self> {
+        Self::parse(xdr, mappings, false)
+    }
+
+    /// Parses the specs in `files`, given as (file name, XDR) pairs, into a
+    /// single `Ast` in which each spec can reference the types and constants
+    /// defined by the others.
+    ///
+    /// Errors name the file they occur in.
+    pub fn from_files<N: AsRef<str>, S: AsRef<str>>(files: &[(N, S)]) -> Result<Self> {
+        let sources = files
+            .iter()
+            .map(|(name, xdr)| Source::new(Some(name.as_ref()), xdr.as_ref()))
+            .collect();
+
+        Self::parse_sources(sources, &BTreeMap::new(), false)
+    }
+
+    /// Parses `xdr` as [`Ast::with_mappings()`], and if `borrowed` is true,
+    /// indexes the types containing strings as generic as well as those
+    /// containing opaque data, as both borrow from a decoded slice.
+    pub(crate) fn parse(
+        xdr: &str,
+        mappings: &BTreeMap<String, String>,
+        borrowed: bool,
+    ) -> Result<Self> {
+        Self::parse_sources(vec![Source::new(None, xdr)], mappings, borrowed)
+    }
+
+    /// Parses the specs in `sources` as [`Ast::parse()`], resolving the
+    /// references between them.
+    pub(crate) fn parse_sources(
+        sources: Vec<Source>,
+        mappings: &BTreeMap<String, String>,
+        borrowed: bool,
+    ) -> Result<Self> {
+        let mut items = Vec::new();
+        let mut locations = Vec::new();
+
+        for (file, source) in sources.iter().enumerate() {
+            let (root, ranges) = parse_source(&source.text).map_err(|e| source.error(e))?;
+
+            let Node::Root(r) = root else { continue };
+            locations.extend(ranges.into_iter().map(|range| Definition { file, range }));
+            items.extend(r);
+        }
+        let mut ast = Node::Root(items);
+
+        let (definitions, constant_files) = check_definitions(&sources, &ast, &locations)?;
+        if let Some(name) = mappings.keys().find(|&k| !definitions.contains_key(k)) {
+            return Err(format!("type mapping for undefined type {}", name).into());
+        }
+        map_types(&mut ast, mappings);
 
         // Build some helpful indexes to answer questions about types when
         // generating the Rust code.
-        let constant_index = ConstantIndex::new(&ast);
-        let generic_index = GenericIndex::new(&ast);
+        let constant_index = ConstantIndex::new(&ast)?;
+        let generic_index = GenericIndex::new(&ast, borrowed);
         let type_index = TypeIndex::new(&ast);
+        let program_index = ProgramIndex::new(&ast);
 
         Ok(Ast {
             constant_index,
             generic_index,
             type_index,
+            program_index,
+            mapping_index: MappingIndex(mappings.clone()),
+            borrowed,
+            sources,
+            definitions,
+            constant_files,
+            emitted: None,
         })
     }
 
@@ -77,6 +177,227 @@ impl Ast {
     pub fn types(&self) -> &TypeIndex {
         &self.type_index
     }
+
+    pub fn programs(&self) -> &ProgramIndex {
+        &self.program_index
+    }
+
+    pub fn mappings(&self) -> &MappingIndex {
+        &self.mapping_index
+    }
+
+    /// Returns true if the generated types borrow from the buffer they are
+    /// decoded from.
+    pub fn is_borrowed(&self) -> bool {
+        self.borrowed
+    }
+
+    /// Returns an [`Error::UnknownType`] pointing at the first reference to
+    /// `name` in the XDR sources.
+    pub(crate) fn unknown_type(&self, name: &str) -> Error {
+        let (source, offset) = self.locate(name);
+        source.error(Error::unknown_type(&source.text, offset, name))
+    }
+}
+
+// Tokenises and walks a single XDR source, returning the root of its AST and
+// the source range of each top-level definition in it.
+fn parse_source(xdr: &str) -> Result<(Node<'_>, Vec<(usize, usize)>)> {
+    let mut root = XDRParser::parse(Rule::item, xdr).map_err(|e| Error::from_pest(xdr, e))?;
+    let root = root
+        .next()
+        .ok_or_else(|| Error::syntax(xdr, 0, "unable to tokenise input"))?;
+
+    check_array_sizes(xdr, root.clone())?;
+
+    // Record the source range of each top-level definition before the
+    // tokens are consumed, so errors can point back at them.
+    let ranges = root
+        .clone()
+        .into_inner()
+        .map(|p| (p.as_span().start(), p.as_span().end()))
+        .collect::<Vec<_>>();
+
+    // Parse into an AST
+    Ok(flatten(walk(root)?, ranges))
+}
+
+// Moves the anonymous types hoisted out of each definition into the root,
+// attributing them to the source range of the definition they were declared
+// in.
+fn flatten<'a>(ast: Node<'a>, ranges: Vec<(usize, usize)>) -> (Node<'a>, Vec<(usize, usize)>) {
+    let items = match ast {
+        Node::Root(r) => r,
+        v => return (v, ranges),
+    };
+
+    let mut flat = Vec::with_capacity(items.len());
+    let mut flat_ranges = Vec::with_capacity(ranges.len());
+    for (item, range) in items.into_iter().zip(ranges) {
+        match item {
+            Node::Definitions(defs) => {
+                flat_ranges.extend(std::iter::repeat_n(range, defs.len()));
+                flat.extend(defs);
+            }
+            v => {
+                flat.push(v);
+                flat_ranges.push(range);
+            }
+        }
+    }
+
+    (Node::Root(flat), flat_ranges)
+}
+
+// Replaces the references to each XDR type in `mappings` with its Rust type
+// path.
+//
+// Union switches are left unchanged, as the discriminant must be decoded as
+// the XDR type to match the case values.
+fn map_types(ast: &mut Node<'_>, mappings: &BTreeMap<String, String>) {
+    let map = |t: &mut BasicType| {
+        if let BasicType::Ident(i) = t {
+            if let Some(path) = mappings.get(i.as_str()) {
+                *i = path.clone();
+            }
+        }
+    };
+
+    let items = match ast {
+        Node::Root(r) => r,
+        _ => return,
+    };
+
+    for item in items.iter_mut() {
+        match item {
+            Node::Struct(v) => {
+                for f in v.fields.iter_mut() {
+                    map(f.field_value.unwrap_array_mut());
+                }
+            }
+            Node::Union(v) => {
+                for c in v.cases.iter_mut().chain(v.default.iter_mut()) {
+                    map(c.field_value.unwrap_array_mut());
+                }
+            }
+            Node::Typedef(v) => map(&mut v.target),
+            Node::Program(p) => {
+                for version in p.versions.iter_mut() {
+                    for proc in version.procedures.iter_mut() {
+                        proc.args
+                            .iter_mut()
+                            .chain(proc.result.iter_mut())
+                            .for_each(map);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// Reject array sizes given as literal values that do not fit in the u32 length
+// prefix of the wire format.
+fn check_array_sizes(xdr: &str, ast: Pair<'_, Rule>) -> Result<()> {
+    match ast.as_rule() {
+        Rule::array_variable | Rule::array_fixed => {
+            for size in ast.into_inner() {
+                let valid = Literal::parse(size.as_str())
+                    .map(|v| u32::try_from(v.value()).is_ok())
+                    .unwrap_or(false);
+
+                if size.as_rule() == Rule::ident_value && !valid {
+                    return Err(Error::array_size(
+                        xdr,
+                        size.as_span().start(),
+                        size.as_str(),
+                    ));
+                }
+            }
+            Ok(())
+        }
+        _ => ast
+            .into_inner()
+            .try_for_each(|pair| check_array_sizes(xdr, pair)),
+    }
+}
+
+// Reject types, constants and programs that are defined more than once,
+// returning the location of each type and program definition, and the source
+// defining each constant.
+//
+// Constants and enum variants share a namespace, as both are used as union
+// case values.
+fn check_definitions(
+    sources: &[Source],
+    ast: &Node<'_>,
+    locations: &[Definition],
+) -> Result<(BTreeMap<String, Definition>, BTreeMap<String, usize>)> {
+    let mut definitions = BTreeMap::new();
+    let mut constant_files = BTreeMap::new();
+    let items = match ast {
+        Node::Root(r) => r,
+        _ => return Ok((definitions, constant_files)),
+    };
+
+    let mut types = BTreeSet::new();
+    let mut constants = BTreeSet::new();
+    let mut programs = BTreeSet::new();
+
+    for (item, location) in items.iter().zip(locations.iter()) {
+        let source = &sources[location.file];
+        let (start, end) = location.range;
+        let duplicate = |name: &str| {
+            let offset = find_ident(&source.text, start, end, name);
+            source.error(Error::duplicate(&source.text, offset, name))
+        };
+
+        let defined = match item {
+            Node::Typedef(v) => Some(v.alias.unwrap_array().as_str()),
+            Node::Struct(v) => Some(v.name()),
+            Node::Union(v) => Some(v.name()),
+            Node::Enum(v) => Some(v.name.as_str()),
+            Node::Program(v) => Some(v.name()),
+            _ => None,
+        };
+        if let Some(name) = defined {
+            definitions
+                .entry(name.to_string())
+                .or_insert_with(|| location.clone());
+        }
+
+        let (set, names) = match item {
+            Node::Typedef(v) => (&mut types, vec![v.alias.unwrap_array().as_str()]),
+            Node::Struct(v) => (&mut types, vec![v.name()]),
+            Node::Union(v) => (&mut types, vec![v.name()]),
+            Node::Enum(v) => {
+                if !types.insert(v.name.as_str()) {
+                    return Err(duplicate(&v.name));
+                }
+                (
+                    &mut constants,
+                    v.variants.iter().map(|v| v.name.as_str()).collect(),
+                )
+            }
+            Node::Constant(vs) => (
+                &mut constants,
+                vec![ident_at(vs, 0).map_err(|e| source.error(e))?],
+            ),
+            Node::Program(v) => (&mut programs, vec![v.name()]),
+            _ => continue,
+        };
+
+        for name in names {
+            if !set.insert(name) {
+                return Err(duplicate(name));
+            }
+            if matches!(item, Node::Enum(_) | Node::Constant(_)) {
+                constant_files.insert(name.to_string(), location.file);
+            }
+        }
+    }
+
+    Ok((definitions, constant_files))
 }
 
 // Recurse into the tokens from the PEG parser, constructing a syntax tree and
@@ -84,39 +405,86 @@ impl Ast {
 //
 // These higher-level types will then be extracted and added to the type index
 // later.
-fn walk(ast: Pair<'_, Rule>) -> Node<'_> {
-    fn collect_values(ast: Pair<'_, Rule>) -> Vec<Node<'_>> {
+//
+// Errors raised when constructing the compound types are attributed to the
+// span of the tokens they were constructed from.
+fn walk(ast: Pair<'_, Rule>) -> Result<Node<'_>> {
+    fn collect_values(ast: Pair<'_, Rule>) -> Result<Vec<Node<'_>>> {
         ast.into_inner().map(walk).collect()
     }
 
-    match ast.as_rule() {
-        Rule::item => Node::Root(collect_values(ast)),
-        Rule::typedef => Node::Typedef(Typedef::new(collect_values(ast))),
-        Rule::constant => Node::Constant(collect_values(ast)),
-        Rule::ident | Rule::ident_const | Rule::ident_value => {
-            if let Ok(t) = BasicType::try_from(ast.as_str()) {
-                Node::Type(t)
-            } else {
-                Node::Ident(ast.as_str())
+    let span = ast.as_span();
+    let at_span = |e: Error| e.with_span(span);
+
+    Ok(match ast.as_rule() {
+        Rule::item => Node::Root(collect_values(ast)?),
+        Rule::typedef => {
+            let mut vs = collect_values(ast)?;
+            let alias = ident_at(&vs, 1).map_err(at_span)?.to_string();
+
+            // An anonymous type is given the name of the typedef, unless it
+            // is the element type of an array.
+            let mut defs = Vec::new();
+            if vs.len() == 2 && hoist_type(&alias, &mut vs[0], &mut defs).map_err(at_span)? {
+                return Ok(Node::Definitions(defs));
             }
+            hoist_type(&format!("{}_element", alias), &mut vs[0], &mut defs).map_err(at_span)?;
+
+            let typedef = Node::Typedef(Typedef::new(vs).map_err(at_span)?);
+            with_hoisted(typedef, defs)
+        }
+        Rule::constant => Node::Constant(collect_values(ast)?),
+        // Integer literals are normalised to their Rust representation, and
+        // identifiers starting with a digit must be valid literals.
+        Rule::ident | Rule::ident_const
+            if !ast.as_str().starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            Node::Type(BasicType::from(ast.as_str()))
+        }
+        Rule::ident | Rule::ident_const | Rule::ident_value => {
+            let v = Literal::parse(ast.as_str()).map_err(at_span)?;
+            Node::Type(BasicType::from(v.to_string()))
         }
-        Rule::enum_type => Node::Enum(Enum::new(collect_values(ast))),
-        Rule::enum_variant => Node::EnumVariant(collect_values(ast)),
-        Rule::array => Node::Array(collect_values(ast)),
+        Rule::enum_type => Node::Enum(Enum::new(collect_values(ast)?).map_err(at_span)?),
+        Rule::enum_variant => Node::EnumVariant(collect_values(ast)?),
+        Rule::array => Node::Array(collect_values(ast)?),
         Rule::array_variable => Node::ArrayVariable(ast.into_inner().as_str()),
         Rule::array_fixed => Node::ArrayFixed(ast.into_inner().as_str()),
-        Rule::struct_type => Node::Struct(Struct::new(collect_values(ast))),
-        Rule::struct_data_field => Node::StructDataField(collect_values(ast)),
-        Rule::union_data_field => Node::UnionDataField(collect_values(ast)),
-        Rule::union => Node::Union(Union::new(collect_values(ast))),
-        Rule::union_case => Node::UnionCase(collect_values(ast)),
-        Rule::union_default => Node::UnionDefault(collect_values(ast)),
+        Rule::struct_type => {
+            let mut vs = collect_values(ast)?;
+            let mut defs = Vec::new();
+            let name = ident_at(&vs, 0).map_err(at_span)?.to_string();
+            hoist(&name, &mut vs, &mut defs).map_err(at_span)?;
+
+            let v = Node::Struct(Struct::new(vs).map_err(at_span)?);
+            with_hoisted(v, defs)
+        }
+        Rule::inline_struct => Node::InlineStruct(collect_values(ast)?),
+        Rule::inline_union => Node::InlineUnion(collect_values(ast)?),
+        Rule::inline_enum => Node::InlineEnum(collect_values(ast)?),
+        Rule::struct_data_field => Node::StructDataField(collect_values(ast)?),
+        Rule::union_data_field => Node::UnionDataField(collect_values(ast)?),
+        Rule::union => {
+            let mut vs = collect_values(ast)?;
+            let mut defs = Vec::new();
+            let name = ident_at(&vs, 0).map_err(at_span)?.to_string();
+            hoist(&name, &mut vs, &mut defs).map_err(at_span)?;
+
+            let v = Node::Union(Union::new(vs).map_err(at_span)?);
+            with_hoisted(v, defs)
+        }
+        Rule::union_case => Node::UnionCase(collect_values(ast)?),
+        Rule::union_default => Node::UnionDefault(collect_values(ast)?),
         Rule::union_void => Node::UnionVoid,
-        Rule::option => Node::Option(collect_values(ast)),
-        Rule::basic_type => {
-            Node::Type(BasicType::try_from(ast.as_str()).expect("unrecognised type"))
+        Rule::option => Node::Option(collect_values(ast)?),
+        Rule::program => Node::Program(Program::new(collect_values(ast)?).map_err(at_span)?),
+        Rule::program_version => {
+            Node::ProgramVersion(Version::new(collect_values(ast)?).map_err(at_span)?)
         }
-        Rule::EOI => Node::EOF,
-        e => panic!("unknown token type {:?}", e),
-    }
+        Rule::procedure => Node::Procedure(Procedure::new(collect_values(ast)?).map_err(at_span)?),
+        Rule::procedure_void => Node::ProcedureVoid,
+        Rule::basic_type => Node::Type(BasicType::from(ast.as_str())),
+        Rule::EOI => Node::Eof,
+        e => return Err(at_span(format!("unknown token type {:?}", e).into())),
+    })
 }
@@ -1,7 +1,15 @@
 use super::*;
+
+/// Returns the ident of the node at `idx` in `nodes`.
+pub(crate) fn ident_at<'a>(nodes: &'a [Node<'a>], idx: usize) -> Result<&'a str> {
+    nodes
+        .get(idx)
+        .ok_or_else(|| format!("missing ident at token {}", idx))?
+        .ident_str()
+}
+
 #[derive(Debug, PartialEq)]
 pub(crate) enum Node<'a> {
-    Ident(&'a str),
     Type(BasicType),
     Option(Vec<Node<'a>>),
     Struct(Struct),
@@ -18,21 +26,26 @@ pub(crate) enum Node<'a> {
     Constant(Vec<Node<'a>>),
     Enum(Enum),
     EnumVariant(Vec<Node<'a>>),
+    InlineStruct(Vec<Node<'a>>),
+    InlineUnion(Vec<Node<'a>>),
+    InlineEnum(Vec<Node<'a>>),
+    /// A definition, followed by the anonymous types hoisted out of it.
+    Definitions(Vec<Node<'a>>),
+    Program(Program),
+    ProgramVersion(Version),
+    Procedure(Procedure),
+    ProcedureVoid,
     Root(Vec<Node<'a>>),
 
-    EOF,
+    Eof,
 }
 
 impl<'a> Node<'a> {
-    pub(crate) fn ident_str(&'a self) -> &'a str {
+    pub(crate) fn ident_str(&'a self) -> Result<&'a str> {
         match self {
-            Node::Ident(v) => match v.trim() {
-                "type" => "type_t",
-                v => v,
-            },
-            Node::Type(v) => v.as_str(),
-            Node::Option(v) => v[0].ident_str(),
-            _ => panic!("not an ident"),
+            Node::Type(v) => Ok(v.as_str()),
+            Node::Option(v) => ident_at(v, 0),
+            v => Err(format!("expected ident, found {:?}", v).into()),
         }
     }
 
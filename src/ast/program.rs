@@ -0,0 +1,266 @@
+use super::*;
+
+/// An ONC RPC program definition, containing one or more versions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub name: String,
+
+    /// The program number, either as a literal value or a constant name.
+    pub number: String,
+    pub versions: Vec<Version>,
+}
+
+impl Program {
+    pub(crate) fn new(vs: Vec<Node<'_>>) -> Result<Self> {
+        let (name, number, vs) = split_definition(vs, "program")?;
+
+        let versions = vs
+            .into_iter()
+            .map(|v| match v {
+                Node::ProgramVersion(v) => Ok(v),
+                v => Err(format!("unexpected token type for program {:?}", v).into()),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name,
+            number,
+            versions,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A single version of an ONC RPC program, defining the set of procedures
+/// available in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version {
+    pub name: String,
+
+    /// The version number, either as a literal value or a constant name.
+    pub number: String,
+    pub procedures: Vec<Procedure>,
+}
+
+impl Version {
+    pub(crate) fn new(vs: Vec<Node<'_>>) -> Result<Self> {
+        let (name, number, vs) = split_definition(vs, "version")?;
+
+        let procedures = vs
+            .into_iter()
+            .map(|v| match v {
+                Node::Procedure(p) => Ok(p),
+                v => Err(format!("unexpected token type for program version {:?}", v).into()),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name,
+            number,
+            procedures,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A remote procedure definition within a program version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Procedure {
+    pub name: String,
+
+    /// The procedure number, either as a literal value or a constant name.
+    pub number: String,
+
+    /// The argument types of the procedure, empty for procedures that take
+    /// `void`.
+    pub args: Vec<BasicType>,
+
+    /// The result type, or `None` if the procedure returns `void`.
+    pub result: Option<BasicType>,
+}
+
+impl Procedure {
+    pub(crate) fn new(mut vs: Vec<Node<'_>>) -> Result<Self> {
+        if vs.len() < 4 {
+            return Err("invalid number of procedure tokens".into());
+        }
+
+        let result = match vs.remove(0) {
+            Node::Type(t) => Some(t),
+            Node::ProcedureVoid => None,
+            v => return Err(format!("unexpected procedure result type {:?}", v).into()),
+        };
+
+        let (name, number, vs) = split_definition(vs, "procedure")?;
+
+        let args = vs
+            .into_iter()
+            .filter_map(|v| match v {
+                Node::Type(t) => Some(Ok(t)),
+                Node::ProcedureVoid => None,
+                v => Some(Err(
+                    format!("unexpected procedure argument type {:?}", v).into()
+                )),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name,
+            number,
+            args,
+            result,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Splits the leading name and trailing number idents from the tokens of a
+/// program, version or procedure definition, returning the remaining tokens.
+fn split_definition<'a>(
+    mut vs: Vec<Node<'a>>,
+    kind: &str,
+) -> Result<(String, String, Vec<Node<'a>>)> {
+    if vs.len() < 2 {
+        return Err(format!("missing {} name or number", kind).into());
+    }
+
+    let name = vs.remove(0).ident_str()?.to_string();
+    let number = match vs.pop() {
+        Some(v) => v.ident_str()?.to_string(),
+        None => return Err(format!("missing {} number", kind).into()),
+    };
+
+    Ok((name, number, vs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! parse {
+        ($input: expr) => {{
+            let ast = XDRParser::parse(Rule::item, $input)
+                .unwrap()
+                .next()
+                .unwrap();
+
+            let root = walk(ast).unwrap();
+            let program = root.into_inner().remove(0);
+            match program {
+                Node::Program(p) => p,
+                _ => panic!("not a program in ast root"),
+            }
+        }};
+    }
+
+    #[test]
+    fn test_program() {
+        let got = parse!(
+            r#"
+        program NFS_PROGRAM {
+            version NFS_V3 {
+                void
+                    NFSPROC3_NULL(void)                    = 0;
+
+                GETATTR3res
+                    NFSPROC3_GETATTR(GETATTR3args)         = 1;
+            } = 3;
+        } = 100003;"#
+        );
+
+        assert_eq!(got.name(), "NFS_PROGRAM");
+        assert_eq!(got.number, "100003");
+        assert_eq!(got.versions.len(), 1);
+
+        let version = &got.versions[0];
+        assert_eq!(version.name(), "NFS_V3");
+        assert_eq!(version.number, "3");
+        assert_eq!(version.procedures.len(), 2);
+
+        assert_eq!(
+            version.procedures[0],
+            Procedure {
+                name: "NFSPROC3_NULL".to_string(),
+                number: "0".to_string(),
+                args: vec![],
+                result: None,
+            }
+        );
+
+        assert_eq!(
+            version.procedures[1],
+            Procedure {
+                name: "NFSPROC3_GETATTR".to_string(),
+                number: "1".to_string(),
+                args: vec![BasicType::Ident("GETATTR3args".to_string())],
+                result: Some(BasicType::Ident("GETATTR3res".to_string())),
+            }
+        );
+    }
+
+    #[test]
+    fn test_program_multiple_versions() {
+        let got = parse!(
+            r#"
+        program MOUNT_PROGRAM {
+            version MOUNT_V1 {
+                void MOUNTPROC_NULL(void) = 0;
+            } = 1;
+            version MOUNT_V3 {
+                void MOUNTPROC3_NULL(void) = 0;
+                mountres3 MOUNTPROC3_MNT(dirpath) = 1;
+            } = MOUNT_V3_NUM;
+        } = 0x000186A5;"#
+        );
+
+        assert_eq!(got.name(), "MOUNT_PROGRAM");
+        assert_eq!(got.number, "0x000186A5");
+        assert_eq!(got.versions.len(), 2);
+
+        assert_eq!(got.versions[0].name(), "MOUNT_V1");
+        assert_eq!(got.versions[0].number, "1");
+        assert_eq!(got.versions[0].procedures.len(), 1);
+
+        assert_eq!(got.versions[1].name(), "MOUNT_V3");
+        assert_eq!(got.versions[1].number, "MOUNT_V3_NUM");
+        assert_eq!(got.versions[1].procedures.len(), 2);
+        assert_eq!(got.versions[1].procedures[1].name(), "MOUNTPROC3_MNT");
+        assert_eq!(got.versions[1].procedures[1].number, "1");
+    }
+
+    #[test]
+    fn test_procedure_basic_types() {
+        let got = parse!(
+            r#"
+        program P {
+            version V {
+                unsigned int PROC_A(int, hyper) = 1;
+                bool PROC_B(string) = 2;
+                voidable PROC_C(void) = 3;
+            } = 1;
+        } = 1;"#
+        );
+
+        let procs = &got.versions[0].procedures;
+        assert_eq!(procs[0].args, vec![BasicType::I32, BasicType::I64]);
+        assert_eq!(procs[0].result, Some(BasicType::U32));
+
+        assert_eq!(procs[1].args, vec![BasicType::String]);
+        assert_eq!(procs[1].result, Some(BasicType::Bool));
+
+        assert_eq!(procs[2].args, vec![]);
+        assert_eq!(
+            procs[2].result,
+            Some(BasicType::Ident("voidable".to_string()))
+        );
+    }
+}
@@ -7,15 +7,15 @@ pub struct Struct {
 }
 
 impl Struct {
-    pub(crate) fn new(vs: Vec<Node<'_>>) -> Self {
-        let name = vs[0].ident_str().to_string();
+    pub(crate) fn new(vs: Vec<Node<'_>>) -> Result<Self> {
+        let name = ident_at(&vs, 0)?.to_string();
 
         let mut fields = Vec::new();
         for v in vs.into_iter().skip(1) {
-            fields.push(StructField::new(v));
+            fields.push(StructField::new(v)?);
         }
 
-        Struct { name, fields }
+        Ok(Struct { name, fields })
     }
 
     pub fn name(&self) -> &str {
@@ -41,13 +41,13 @@ pub struct StructField {
 }
 
 impl StructField {
-    pub(crate) fn new(v: Node<'_>) -> Self {
+    pub(crate) fn new(v: Node<'_>) -> Result<Self> {
         let f = match v {
             Node::StructDataField(f) => f,
-            e => panic!("not a struct field: {:?}", e),
+            e => return Err(format!("not a struct field: {:?}", e).into()),
         };
 
-        match f.as_slice() {
+        let field = match f.as_slice() {
             [Node::Type(rhs), Node::Type(BasicType::Ident(lhs))] => Self {
                 field_name: lhs.to_string(),
                 field_value: ArrayType::None(rhs.to_owned()),
@@ -70,9 +70,9 @@ impl StructField {
                 is_optional: false,
             },
             [Node::Type(rhs), Node::Option(opt)] => {
-                let lhs = match &opt[0] {
-                    Node::Type(BasicType::Ident(lhs)) => lhs,
-                    _ => panic!("unexpected struct field option layout"),
+                let lhs = match opt.first() {
+                    Some(Node::Type(BasicType::Ident(lhs))) => lhs,
+                    _ => return Err("unexpected struct field option layout".into()),
                 };
 
                 Self {
@@ -81,8 +81,10 @@ impl StructField {
                     is_optional: true,
                 }
             }
-            _ => panic!("invalid number of struct field tokens"),
-        }
+            _ => return Err("invalid number of struct field tokens".into()),
+        };
+
+        Ok(field)
     }
 
     pub fn contains_opaque(&self) -> bool {
@@ -91,6 +93,7 @@ impl StructField {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -101,7 +104,7 @@ mod tests {
                 .next()
                 .unwrap();
 
-            let root = walk(ast);
+            let root = walk(ast).unwrap();
             let union = root.into_inner().remove(0);
             match union {
                 Node::Struct(u) => u,
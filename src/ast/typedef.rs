@@ -7,29 +7,26 @@ pub struct Typedef {
 }
 
 impl Typedef {
-    pub(crate) fn new(mut vs: Vec<Node<'_>>) -> Self {
+    pub(crate) fn new(vs: Vec<Node<'_>>) -> Result<Self> {
+        let mut vs = vs.into_iter();
+
         // Extract the target type
-        let target = match vs.remove(0) {
-            Node::Type(t) => t,
-            _ => unreachable!("incorrect type in typedef"),
+        let target = match vs.next() {
+            Some(Node::Type(t)) => t,
+            t => return Err(format!("incorrect type in typedef {:?}", t).into()),
         };
 
         // Extract the defined alias
-        let alias = match vs.remove(0) {
-            Node::Type(t) => t,
-            _ => unreachable!("incorrect type in typedef"),
+        let alias = match vs.next() {
+            Some(Node::Type(t)) => t,
+            t => return Err(format!("incorrect type in typedef {:?}", t).into()),
         };
 
         // Optionally, extract the array definition
-        let alias = if !vs.is_empty() {
-            match vs.remove(0) {
+        let alias = match vs.next() {
+            Some(v) => match v {
                 Node::ArrayFixed(s) => ArrayType::FixedSize(alias, ArraySize::from(s)),
 
-                // Typedefs to opaque types include a variable array identifier so the
-                // caller knows to read the length prefix bytes. This is already handled
-                // by the opaque reader however, so map this to a "no array" wrapper.
-                Node::ArrayVariable(_) if target.is_opaque() => ArrayType::None(alias),
-
                 Node::ArrayVariable(s) => ArrayType::VariableSize(
                     alias,
                     match s.trim() {
@@ -37,12 +34,23 @@ impl Typedef {
                         s => Some(ArraySize::from(s)),
                     },
                 ),
-                t => unreachable!("incorrect type in typedef {:?}", t),
-            }
-        } else {
-            ArrayType::None(alias)
+                t => return Err(format!("incorrect type in typedef {:?}", t).into()),
+            },
+            None => ArrayType::None(alias),
         };
 
-        Self { target, alias }
+        Ok(Self { target, alias })
+    }
+
+    /// Returns the target wrapped in the array defined on the alias, the type
+    /// of the value held by the generated newtype.
+    pub fn target_array(&self) -> ArrayType<BasicType> {
+        match &self.alias {
+            ArrayType::None(_) => ArrayType::None(self.target.clone()),
+            ArrayType::FixedSize(_, s) => ArrayType::FixedSize(self.target.clone(), s.clone()),
+            ArrayType::VariableSize(_, s) => {
+                ArrayType::VariableSize(self.target.clone(), s.clone())
+            }
+        }
     }
 }
@@ -16,16 +16,20 @@ pub struct Union {
 }
 
 impl Union {
-    pub(crate) fn new(vs: Vec<Node<'_>>) -> Self {
-        let name = vs[0].ident_str().to_string();
+    pub(crate) fn new(vs: Vec<Node<'_>>) -> Result<Self> {
+        let name = ident_at(&vs, 0)?.to_string();
 
         let mut cases = Vec::new();
         let mut void_cases = Vec::new();
         let mut default = None;
 
+        let var_type = match vs.get(1) {
+            Some(Node::Type(t)) => t.clone(),
+            v => return Err(format!("unexpected union switch type {:?}", v).into()),
+        };
         let switch = UnionSwitch {
-            var_name: vs[2].ident_str().to_string(),
-            var_type: BasicType::from(vs[1].ident_str().to_string()),
+            var_name: ident_at(&vs, 2)?.to_string(),
+            var_type,
         };
 
         // Collect the set of case values that "fallthrough" to the eventual
@@ -35,13 +39,13 @@ impl Union {
         for v in vs.into_iter().skip(3) {
             let mut is_default_case = false;
             let stmt = match v {
-                Node::UnionCase(nodes) => CaseStmt::parse(case_values, nodes),
+                Node::UnionCase(nodes) => CaseStmt::parse(case_values, nodes)?,
                 Node::UnionDefault(nodes) => {
                     is_default_case = true;
                     case_values.push("default".to_string());
-                    CaseStmt::parse(case_values, nodes)
+                    CaseStmt::parse(case_values, nodes)?
                 }
-                v => panic!("unexpected token type for union {:?}", v),
+                v => return Err(format!("unexpected token type for union {:?}", v).into()),
             };
 
             match stmt {
@@ -59,13 +63,13 @@ impl Union {
             case_values = Vec::new()
         }
 
-        Union {
+        Ok(Union {
             name,
             cases,
             default,
             void_cases,
             switch,
-        }
+        })
     }
 
     pub fn name(&self) -> &str {
@@ -102,14 +106,17 @@ pub struct UnionCase {
 }
 
 impl UnionCase {
-    pub(crate) fn new(case_values: Vec<String>, field: Vec<Node<'_>>) -> Self {
+    pub(crate) fn new(case_values: Vec<String>, field: Vec<Node<'_>>) -> Result<Self> {
         match field.as_slice() {
-            [Node::Type(t), Node::Type(BasicType::Ident(l))] => Self {
+            [Node::Type(t), Node::Type(BasicType::Ident(l))] => Ok(Self {
                 case_values,
                 field_name: l.to_string(),
                 field_value: ArrayType::None(t.to_owned()),
-            },
-            _ => panic!("invalid number of union field tokens"),
+            }),
+            [_, Node::Type(BasicType::Ident(l)), ..] => {
+                Err(format!("array types are not supported in union case field {}", l).into())
+            }
+            _ => Err("invalid number of union field tokens".into()),
         }
     }
 
@@ -130,28 +137,32 @@ enum CaseStmt {
 }
 
 impl CaseStmt {
-    fn parse(mut case_values: Vec<String>, mut nodes: Vec<Node<'_>>) -> Self {
+    fn parse(mut case_values: Vec<String>, mut nodes: Vec<Node<'_>>) -> Result<Self> {
+        if nodes.is_empty() {
+            return Err("empty union case".into());
+        }
+
         match nodes.remove(0) {
             Node::Type(t) => case_values.push(t.as_str().to_string()),
             Node::UnionVoid => {
                 // No ident, this is a default case
-                return Self::Void(case_values);
+                return Ok(Self::Void(case_values));
             }
             Node::UnionDataField(nodes) => {
                 // No ident, this is a default case
-                return Self::Defined(UnionCase::new(case_values, nodes));
+                return Ok(Self::Defined(UnionCase::new(case_values, nodes)?));
             }
-            _ => unreachable!(),
+            v => return Err(format!("unexpected token type for union case {:?}", v).into()),
         };
 
         if nodes.is_empty() {
-            return Self::Fallthrough(case_values);
+            return Ok(Self::Fallthrough(case_values));
         }
 
         match nodes.remove(0) {
-            Node::UnionDataField(nodes) => Self::Defined(UnionCase::new(case_values, nodes)),
-            Node::UnionVoid => Self::Void(case_values),
-            _ => unreachable!(),
+            Node::UnionDataField(nodes) => Ok(Self::Defined(UnionCase::new(case_values, nodes)?)),
+            Node::UnionVoid => Ok(Self::Void(case_values)),
+            v => Err(format!("unexpected token type for union case {:?}", v).into()),
         }
     }
 }
@@ -167,7 +178,7 @@ mod tests {
                 .next()
                 .unwrap();
 
-            let root = walk(ast);
+            let root = walk(ast).unwrap();
             let union = root.into_inner().remove(0);
             match union {
                 Node::Union(u) => u,
@@ -404,4 +415,24 @@ mod tests {
             BasicType::Ident("nfsstat4".to_string())
         );
     }
+
+    #[test]
+    fn test_union_case_array() {
+        let input = r#"
+        union u switch (int type) {
+            case 1:
+                opaque data<>;
+        };"#;
+
+        match Ast::new(input).unwrap_err() {
+            crate::Error::InvalidDefinition { message, span, .. } => {
+                assert_eq!(
+                    message,
+                    "array types are not supported in union case field data"
+                );
+                assert_eq!(span.line, 2);
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
 }
@@ -0,0 +1,923 @@
+use super::*;
+use crate::error::find_ident;
+use crate::Error;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+impl Ast {
+    /// Checks the references between definitions in the spec, returning a
+    /// diagnostic for every problem found.
+    ///
+    /// This reports:
+    ///
+    /// * Types that are referenced but never defined.
+    /// * Array sizes, union case values, enum values and program, version or
+    ///   procedure numbers that name an undefined constant.
+    /// * Recursive types that contain themselves without an optional (`*`) or
+    ///   variable length array breaking the recursion, and therefore have an
+    ///   infinite size.
+    /// * Program versions sharing a name, as the types generated for each
+    ///   version are named after it.
+    /// * Fixed length strings, which XDR does not define.
+    /// * Unions switching on a type other than an integer, `bool` or enum.
+    /// * Enum variants sharing a value, which cannot be distinguished.
+    /// * Union cases sharing a value, and procedures sharing a number within
+    ///   a program version, as only the first can ever be decoded.
+    ///
+    /// The diagnostics are ordered by their position in the sources.
+    pub fn validate(&self) -> Vec<Error> {
+        let mut v = Validator {
+            ast: self,
+            seen: BTreeSet::new(),
+            versions: BTreeSet::new(),
+            errors: Vec::new(),
+        };
+
+        for (name, t) in self.types().0.iter() {
+            v.check_definition(name, t);
+        }
+
+        for program in self.programs().iter() {
+            v.check_program(program);
+        }
+
+        v.check_recursion();
+
+        let mut errors = v.errors;
+        errors.sort_by_key(|(file, e)| (*file, e.span().map(|s| (s.line, s.column))));
+        errors.into_iter().map(|(_, e)| e).collect()
+    }
+}
+
+struct Validator<'a> {
+    ast: &'a Ast,
+
+    // (definition, name) pairs already reported, so repeated references to the
+    // same missing name within a definition are reported once.
+    seen: BTreeSet<(String, String)>,
+
+    // The names of the program versions checked so far.
+    versions: BTreeSet<&'a str>,
+
+    // The errors, and the index of the source each occurs in.
+    errors: Vec<(usize, Error)>,
+}
+
+impl<'a> Validator<'a> {
+    /// Returns the index of the source containing the definition of `def`,
+    /// and the offset of the reference to `name` within it, skipping the
+    /// first `skip` references.
+    fn locate(&self, def: &str, name: &str, skip: usize) -> (usize, usize) {
+        let (file, mut start, end) = match self.ast.definitions.get(def) {
+            Some(d) => (d.file, d.range.0, d.range.1),
+            None => (0, 0, self.ast.sources[0].text.len()),
+        };
+
+        let source = &self.ast.sources[file].text;
+        for _ in 0..skip {
+            start = find_ident(source, start, end, name) + name.len();
+        }
+        (file, find_ident(source, start.min(end), end, name))
+    }
+
+    fn report<F>(&mut self, def: &str, name: &str, f: F)
+    where
+        F: Fn(&str, usize, String) -> Error,
+    {
+        if !self.seen.insert((def.to_string(), name.to_string())) {
+            return;
+        }
+
+        self.report_nth(def, name, 0, f);
+    }
+
+    /// Reports an error at the reference to `name` following the first `skip`
+    /// references in `def`, even if `name` has been reported before.
+    fn report_nth<F>(&mut self, def: &str, name: &str, skip: usize, f: F)
+    where
+        F: Fn(&str, usize, String) -> Error,
+    {
+        let (file, offset) = self.locate(def, name, skip);
+        let source = &self.ast.sources[file];
+        self.errors.push((
+            file,
+            source.error(f(&source.text, offset, name.to_string())),
+        ));
+    }
+
+    fn check_definition(&mut self, def: &str, t: &AstType) {
+        match t {
+            AstType::Struct(s) => {
+                for f in s.fields.iter() {
+                    self.check_array(def, &f.field_value);
+                    if let ArrayType::FixedSize(BasicType::String, _) = f.field_value {
+                        self.report_fixed_string(def, &f.field_name);
+                    }
+                }
+            }
+            AstType::Union(u) => {
+                self.check_type(def, &u.switch.var_type);
+                self.check_switch(def, u);
+                for c in u.cases.iter().chain(u.default.iter()) {
+                    self.check_array(def, &c.field_value);
+                }
+
+                let values = u
+                    .cases
+                    .iter()
+                    .flat_map(|c| c.case_values.iter())
+                    .chain(u.void_cases.iter())
+                    .filter(|v| *v != "default");
+
+                // The resolved case values, and the first case with each.
+                let mut seen = BTreeMap::new();
+                let mut names = Vec::new();
+                for v in values {
+                    self.check_case_value(def, v);
+
+                    let value = match v.as_str() {
+                        "TRUE" => Some(1),
+                        "FALSE" => Some(0),
+                        v => resolve_value(self.ast, v),
+                    };
+                    if let Some(value) = value {
+                        match seen.entry(value) {
+                            Entry::Vacant(e) => {
+                                e.insert(v.as_str());
+                            }
+                            Entry::Occupied(e) => {
+                                let first = *e.get();
+                                let skip = names.iter().filter(|n| **n == v).count();
+                                self.report_nth(def, v, skip, |source, offset, name| {
+                                    Error::invalid(
+                                        source,
+                                        offset,
+                                        format!(
+                                            "union case {} has the same value as {}",
+                                            name, first
+                                        ),
+                                    )
+                                });
+                            }
+                        }
+                    }
+                    names.push(v);
+                }
+            }
+            AstType::Enum(e) => {
+                let mut values = BTreeMap::new();
+                for v in e.variants.iter() {
+                    if let VariantValue::String(c) = &v.value {
+                        if self.ast.constants().get(c).is_none() {
+                            self.report(def, c, Error::unknown_constant);
+                        }
+                    }
+
+                    let value = match variant_value(self.ast, &v.value) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    match values.entry(value) {
+                        Entry::Vacant(e) => {
+                            e.insert(v.name.as_str());
+                        }
+                        Entry::Occupied(e) => {
+                            let first = *e.get();
+                            self.report(def, &v.name, |source, offset, name| {
+                                Error::invalid(
+                                    source,
+                                    offset,
+                                    format!(
+                                        "enum variant {} has the same value as {}",
+                                        name, first
+                                    ),
+                                )
+                            });
+                        }
+                    }
+                }
+            }
+            AstType::Typedef(t) => {
+                // Self-referencing typedefs are not generated, and need no
+                // target.
+                if t.target.as_str() != def {
+                    self.check_type(def, &t.target);
+                }
+                self.check_size(def, &t.alias);
+                if let (BasicType::String, ArrayType::FixedSize(_, _)) = (&t.target, &t.alias) {
+                    self.report_fixed_string(def, def);
+                }
+            }
+        }
+    }
+
+    fn check_program(&mut self, program: &'a Program) {
+        let def = program.name();
+        self.check_number(def, &program.number);
+
+        for version in program.versions.iter() {
+            self.check_number(def, &version.number);
+            if !self.versions.insert(version.name()) {
+                self.report(def, version.name(), Error::duplicate);
+            }
+
+            let mut numbers = BTreeMap::new();
+            for p in version.procedures.iter() {
+                self.check_number(def, &p.number);
+                for t in p.args.iter().chain(p.result.iter()) {
+                    self.check_type(def, t);
+                }
+
+                let number = match resolve_value(self.ast, &p.number) {
+                    Some(number) => number,
+                    None => continue,
+                };
+                match numbers.entry(number) {
+                    Entry::Vacant(e) => {
+                        e.insert(p.name.as_str());
+                    }
+                    Entry::Occupied(e) => {
+                        // Procedure names may be repeated in other versions
+                        // of the program.
+                        let first = *e.get();
+                        let skip = program
+                            .versions
+                            .iter()
+                            .flat_map(|v| v.procedures.iter())
+                            .take_while(|x| !std::ptr::eq(*x, p))
+                            .filter(|x| x.name == p.name)
+                            .count();
+                        self.report_nth(def, &p.name, skip, |source, offset, name| {
+                            Error::invalid(
+                                source,
+                                offset,
+                                format!("procedure {} has the same number as {}", name, first),
+                            )
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn report_fixed_string(&mut self, def: &str, field: &str) {
+        self.report(def, field, |source, offset, field| {
+            Error::invalid(
+                source,
+                offset,
+                format!("string {} cannot have a fixed length", field),
+            )
+        });
+    }
+
+    fn check_array(&mut self, def: &str, t: &ArrayType<BasicType>) {
+        self.check_type(def, t.unwrap_array());
+        self.check_size(def, t);
+    }
+
+    fn check_type(&mut self, def: &str, t: &BasicType) {
+        if let BasicType::Ident(name) = t {
+            if self.ast.types().get(name).is_none() && self.ast.mappings().source(name).is_none() {
+                self.report(def, name, Error::unknown_type);
+            }
+        }
+    }
+
+    // Array sizes are written as `SIZE as usize`, so must name a constant
+    // rather than an enum variant.
+    fn check_size(&mut self, def: &str, t: &ArrayType<BasicType>) {
+        let size = match t {
+            ArrayType::FixedSize(_, ArraySize::Constant(c)) => c,
+            ArrayType::VariableSize(_, Some(ArraySize::Constant(c))) => c,
+            _ => return,
+        };
+
+        match self.ast.constants().get(size) {
+            Some(ConstantType::ConstValue(v)) if u32::try_from(v.value()).is_err() => {
+                self.report(def, size, Error::array_size)
+            }
+            Some(ConstantType::ConstValue(_)) => {}
+            _ => self.report(def, size, Error::unknown_constant),
+        }
+    }
+
+    // Typedefs are followed to the type they alias, and undefined types are
+    // reported by check_type.
+    fn check_switch(&mut self, def: &str, u: &Union) {
+        let mut t = &u.switch.var_type;
+        for _ in 0..=self.ast.types().0.len() {
+            match t {
+                BasicType::U32 | BasicType::I32 | BasicType::U64 | BasicType::I64 => return,
+                BasicType::Bool => return,
+                BasicType::Ident(name) => match self.ast.types().get(name) {
+                    Some(AstType::Typedef(v)) if matches!(v.alias, ArrayType::None(_)) => {
+                        t = &v.target
+                    }
+                    Some(AstType::Enum(_)) | None => return,
+                    Some(_) => break,
+                },
+                _ => break,
+            }
+        }
+
+        self.report(def, &u.switch.var_name, |source, offset, name| {
+            Error::invalid(
+                source,
+                offset,
+                format!("union switch {} must be an integer, bool or enum", name),
+            )
+        });
+    }
+
+    fn check_case_value(&mut self, def: &str, v: &str) {
+        if is_literal(v) || v == "TRUE" || v == "FALSE" {
+            return;
+        }
+
+        if self.ast.constants().get(v).is_none() {
+            self.report(def, v, Error::unknown_constant);
+        }
+    }
+
+    fn check_number(&mut self, def: &str, v: &str) {
+        if is_literal(v) {
+            return;
+        }
+
+        if !matches!(
+            self.ast.constants().get(v),
+            Some(ConstantType::ConstValue(_))
+        ) {
+            self.report(def, v, Error::unknown_constant);
+        }
+    }
+
+    fn check_recursion(&mut self) {
+        // Map each type to the types it contains by value.
+        let ast = self.ast;
+        let edges = ast
+            .types()
+            .0
+            .iter()
+            .map(|(name, t)| (name.as_str(), value_fields(t)))
+            .collect::<BTreeMap<_, _>>();
+
+        for name in edges.keys() {
+            let mut visited = BTreeSet::new();
+            let mut stack = edges[name].clone();
+
+            while let Some(next) = stack.pop() {
+                if next == *name {
+                    self.report(name, name, Error::recursive_type);
+                    break;
+                }
+
+                if visited.insert(next) {
+                    stack.extend(edges.get(next).into_iter().flatten());
+                }
+            }
+        }
+    }
+}
+
+/// Returns the names of the types contained by value in `t`, excluding those
+/// behind an optional or variable length array, which are heap allocated.
+fn value_fields(t: &AstType) -> Vec<&str> {
+    fn by_value(t: &ArrayType<BasicType>) -> Option<&str> {
+        match t {
+            ArrayType::VariableSize(_, _) => None,
+            ArrayType::None(t) | ArrayType::FixedSize(t, _) => match t {
+                BasicType::Ident(name) => Some(name.as_str()),
+                _ => None,
+            },
+        }
+    }
+
+    match t {
+        AstType::Struct(s) => s
+            .fields
+            .iter()
+            .filter(|f| !f.is_optional)
+            .filter_map(|f| by_value(&f.field_value))
+            .collect(),
+        AstType::Union(u) => u
+            .cases
+            .iter()
+            .chain(u.default.iter())
+            .filter_map(|c| by_value(&c.field_value))
+            .collect(),
+        AstType::Typedef(t) => match (&t.alias, &t.target) {
+            (ArrayType::VariableSize(_, _), _) => vec![],
+            (alias, BasicType::Ident(name)) if alias.unwrap_array().as_str() != name => {
+                vec![name.as_str()]
+            }
+            _ => vec![],
+        },
+        AstType::Enum(_) => vec![],
+    }
+}
+
+/// Resolves a literal, or the name of a constant or enum variant, to its
+/// value.
+///
+/// Returns `None` if `v` names an undefined constant, or an enum variant whose
+/// value cannot be resolved.
+fn resolve_value(ast: &Ast, v: &str) -> Option<i128> {
+    // Follow at most as many references as there are constants, as enum
+    // variants may refer to each other in a cycle.
+    let mut v = v;
+    for _ in 0..=ast.constants().iter().count() {
+        if is_literal(v) {
+            return Literal::parse(v).ok().map(|l| l.value());
+        }
+
+        match ast.constants().get(v)? {
+            ConstantType::ConstValue(c) => return Some(c.value()),
+            ConstantType::EnumValue { enum_name, variant } => {
+                let value = match ast.types().get(enum_name)? {
+                    AstType::Enum(e) => &e.variants.iter().find(|x| &x.name == variant)?.value,
+                    _ => return None,
+                };
+                match value {
+                    VariantValue::Numeric(n) => return Some(i128::from(*n)),
+                    VariantValue::String(c) => v = c,
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Resolves the value of an enum variant.
+fn variant_value(ast: &Ast, v: &VariantValue) -> Option<i128> {
+    match v {
+        VariantValue::Numeric(n) => Some(i128::from(*n)),
+        VariantValue::String(c) => resolve_value(ast, c),
+    }
+}
+
+fn is_literal(v: &str) -> bool {
+    v.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+
+    fn validate(input: &str) -> Vec<(String, Span)> {
+        Ast::new(input)
+            .unwrap()
+            .validate()
+            .into_iter()
+            .map(|e| match e {
+                Error::UnknownType { name, span, .. } => (format!("type {}", name), span),
+                Error::UnknownConstant { name, span, .. } => (format!("const {}", name), span),
+                Error::RecursiveType { name, span, .. } => (format!("recursive {}", name), span),
+                Error::InvalidArraySize { size, span, .. } => (format!("size {}", size), span),
+                Error::DuplicateDefinition { name, span, .. } => {
+                    (format!("duplicate {}", name), span)
+                }
+                Error::InvalidDefinition { message, span, .. } => (message, span),
+                e => panic!("unexpected error {:?}", e),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let got = validate(
+            r#"
+            const SIZE = 4;
+            enum status {
+                OK = 0,
+                ERR = SIZE
+            };
+            typedef opaque handle<SIZE>;
+            struct entry {
+                handle h;
+                uint32_t values[SIZE];
+                entry *next;
+                entry others<>;
+            };
+            union result switch (status s) {
+                case OK:
+                    entry e;
+                case ERR:
+                case 42:
+                    void;
+            };
+            program P {
+                version V {
+                    result GET(handle) = SIZE;
+                } = 1;
+            } = 0x20000001;
+        "#,
+        );
+
+        assert_eq!(got, vec![]);
+    }
+
+    #[test]
+    fn test_typedef_unresolvable() {
+        let got = validate(
+            r#"
+            typedef old new;
+        "#,
+        );
+
+        assert_eq!(
+            got,
+            vec![(
+                "type old".to_string(),
+                Span {
+                    line: 2,
+                    column: 21
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_unknown_types() {
+        let got = validate(
+            r#"struct thing {
+    missing a;
+    uint32_t b;
+    missing c;
+};
+union u switch (kind k) {
+    case 1:
+        other o;
+};
+program P {
+    version V {
+        void PROC(args) = 1;
+    } = 1;
+} = 1;"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![
+                ("type missing".to_string(), Span { line: 2, column: 5 }),
+                (
+                    "type kind".to_string(),
+                    Span {
+                        line: 6,
+                        column: 17
+                    }
+                ),
+                ("type other".to_string(), Span { line: 8, column: 9 }),
+                (
+                    "type args".to_string(),
+                    Span {
+                        line: 12,
+                        column: 19
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_constants() {
+        let got = validate(
+            r#"enum e {
+    A = 1,
+    B = UNDEFINED_VALUE
+};
+struct s {
+    opaque data[UNDEFINED_SIZE];
+    uint32_t list<A>;
+};
+union u switch (e v) {
+    case A:
+        void;
+    case UNDEFINED_CASE:
+        void;
+};
+program P {
+    version V {
+        void PROC(void) = UNDEFINED_PROC;
+    } = 1;
+} = 1;"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "const UNDEFINED_VALUE".to_string(),
+                    Span { line: 3, column: 9 }
+                ),
+                (
+                    "const UNDEFINED_SIZE".to_string(),
+                    Span {
+                        line: 6,
+                        column: 17
+                    }
+                ),
+                (
+                    "const A".to_string(),
+                    Span {
+                        line: 7,
+                        column: 19
+                    }
+                ),
+                (
+                    "const UNDEFINED_CASE".to_string(),
+                    Span {
+                        line: 12,
+                        column: 10
+                    }
+                ),
+                (
+                    "const UNDEFINED_PROC".to_string(),
+                    Span {
+                        line: 17,
+                        column: 27
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recursive_types() {
+        let got = validate(
+            r#"struct direct {
+    direct d;
+};
+struct a {
+    b inner;
+};
+union b switch (int v) {
+    case 1:
+        a inner;
+};
+typedef a alias[2];
+struct broken {
+    broken *next;
+    broken list<>;
+};"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![
+                ("recursive direct".to_string(), Span { line: 1, column: 8 }),
+                ("recursive a".to_string(), Span { line: 4, column: 8 }),
+                ("recursive b".to_string(), Span { line: 7, column: 7 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_constant_size() {
+        let got = validate(
+            r#"const NEGATIVE = -1;
+struct s {
+    opaque data<NEGATIVE>;
+};"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![(
+                "size NEGATIVE".to_string(),
+                Span {
+                    line: 3,
+                    column: 17
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_version() {
+        let got = validate(
+            r#"program A {
+    version V1 {
+        void PROC(void) = 1;
+    } = 1;
+} = 1;
+program B {
+    version V1 {
+        void PROC(void) = 1;
+    } = 1;
+} = 2;"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![(
+                "duplicate V1".to_string(),
+                Span {
+                    line: 7,
+                    column: 13
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_fixed_length_string() {
+        let got = validate(
+            r#"struct s {
+    int a;
+    string x[4];
+};
+typedef string name[8];"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "string x cannot have a fixed length".to_string(),
+                    Span {
+                        line: 3,
+                        column: 12
+                    }
+                ),
+                (
+                    "string name cannot have a fixed length".to_string(),
+                    Span {
+                        line: 5,
+                        column: 16
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_union_switch() {
+        let got = validate(
+            r#"enum kind {
+    A = 1
+};
+typedef kind alias;
+typedef double real;
+typedef int ints<>;
+union a switch (alias k) {
+    case A:
+        void;
+};
+union b switch (real r) {
+    case 1:
+        void;
+};
+union c switch (ints i) {
+    case 1:
+        void;
+};"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "union switch r must be an integer, bool or enum".to_string(),
+                    Span {
+                        line: 11,
+                        column: 22
+                    }
+                ),
+                (
+                    "union switch i must be an integer, bool or enum".to_string(),
+                    Span {
+                        line: 15,
+                        column: 22
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_enum_value() {
+        let got = validate(
+            r#"const ONE = 1;
+enum e {
+    A = 1,
+    B = 2,
+    C = ONE
+};
+enum f {
+    D = 0x10,
+    E = 16,
+    F = D
+};"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "enum variant C has the same value as A".to_string(),
+                    Span { line: 5, column: 5 }
+                ),
+                (
+                    "enum variant E has the same value as D".to_string(),
+                    Span { line: 9, column: 5 }
+                ),
+                (
+                    "enum variant F has the same value as D".to_string(),
+                    Span {
+                        line: 10,
+                        column: 5
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_case_value() {
+        let got = validate(
+            r#"const ONE = 1;
+enum kind {
+    A = 1,
+    B = 2
+};
+union u switch (kind k) {
+    case A:
+        int a;
+    case B:
+    case ONE:
+        void;
+};
+union v switch (int s) {
+    case 1:
+        int a;
+    case 2:
+        int b;
+    case 1:
+        void;
+};"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "union case ONE has the same value as A".to_string(),
+                    Span {
+                        line: 10,
+                        column: 10
+                    }
+                ),
+                (
+                    "union case 1 has the same value as 1".to_string(),
+                    Span {
+                        line: 18,
+                        column: 10
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_procedure_number() {
+        let got = validate(
+            r#"const PROC_B = 2;
+program P {
+    version V1 {
+        void A(void) = 1;
+        void B(void) = 2;
+    } = 1;
+    version V2 {
+        void A(void) = 1;
+        void B(void) = 2;
+        void C(void) = PROC_B;
+        void A(void) = 1;
+    } = 2;
+} = 1;"#,
+        );
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "procedure C has the same number as B".to_string(),
+                    Span {
+                        line: 10,
+                        column: 14
+                    }
+                ),
+                (
+                    "procedure A has the same number as A".to_string(),
+                    Span {
+                        line: 11,
+                        column: 14
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+    /// A `string<N>` of at most `N` bytes.
+    ///
+    /// A `BoundedString` cannot be constructed from a longer string, so
+    /// decoded and encoded values are always within the bound of the spec.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct BoundedString<const N: usize>(String);
+
+    impl<const N: usize> BoundedString<N> {
+        /// The maximum length of the string in bytes.
+        pub const MAX: usize = N;
+
+        /// Returns `v` as a `BoundedString`, or [`Error::InvalidLength`] if
+        /// it is longer than `N` bytes.
+        pub fn new(v: String) -> Result<Self, Error> {
+            if v.len() > N {
+                return Err(Error::InvalidLength);
+            }
+            Ok(Self(v))
+        }
+
+        pub fn as_str(&self) -> &str {
+            &self.0
+        }
+
+        pub fn into_inner(self) -> String {
+            self.0
+        }
+    }
+
+    impl<const N: usize> core::ops::Deref for BoundedString<N> {
+        type Target = str;
+
+        fn deref(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl<const N: usize> TryFrom<String> for BoundedString<N> {
+        type Error = Error;
+
+        fn try_from(v: String) -> Result<Self, Self::Error> {
+            Self::new(v)
+        }
+    }
+
+    impl<const N: usize> TryFrom<&str> for BoundedString<N> {
+        type Error = Error;
+
+        fn try_from(v: &str) -> Result<Self, Self::Error> {
+            Self::new(v.to_string())
+        }
+    }
+
+    impl<const N: usize> From<BoundedString<N>> for String {
+        fn from(v: BoundedString<N>) -> String {
+            v.0
+        }
+    }
+
+    impl<const N: usize> Encode for BoundedString<N> {
+        fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+            buf.write_string(&self.0, Some(N))
+        }
+    }
+
+    impl<const N: usize> WireSize for BoundedString<N> {
+        fn wire_size(&self) -> usize {
+            self.0.wire_size()
+        }
+    }
+
+    /// An `opaque<N>` of at most `N` bytes.
+    ///
+    /// A `BoundedOpaque` cannot be constructed from a longer value, so decoded
+    /// and encoded values are always within the bound of the spec.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct BoundedOpaque<T, const N: usize>(T);
+
+    impl<T: AsRef<[u8]>, const N: usize> BoundedOpaque<T, N> {
+        /// The maximum length of the opaque in bytes.
+        pub const MAX: usize = N;
+
+        /// Returns `v` as a `BoundedOpaque`, or [`Error::InvalidLength`] if
+        /// it is longer than `N` bytes.
+        pub fn new(v: T) -> Result<Self, Error> {
+            if v.as_ref().len() > N {
+                return Err(Error::InvalidLength);
+            }
+            Ok(Self(v))
+        }
+
+        pub fn get_ref(&self) -> &T {
+            &self.0
+        }
+
+        pub fn into_inner(self) -> T {
+            self.0
+        }
+    }
+
+    impl<T: AsRef<[u8]>, const N: usize> AsRef<[u8]> for BoundedOpaque<T, N> {
+        fn as_ref(&self) -> &[u8] {
+            self.0.as_ref()
+        }
+    }
+
+    impl<T: AsRef<[u8]>, const N: usize> Encode for BoundedOpaque<T, N> {
+        fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+            buf.write_variable_bytes(self.0.as_ref(), Some(N))
+        }
+    }
+
+    impl<T: WireSize, const N: usize> WireSize for BoundedOpaque<T, N> {
+        fn wire_size(&self) -> usize {
+            self.0.wire_size()
+        }
+    }
+
+    /// An `opaque[N]` of exactly `N` bytes.
+    ///
+    /// A `FixedOpaque` cannot be constructed from a value of any other length,
+    /// so decoded and encoded values always have the length of the spec.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct FixedOpaque<T, const N: usize>(T);
+
+    impl<T: AsRef<[u8]>, const N: usize> FixedOpaque<T, N> {
+        /// The length of the opaque in bytes.
+        pub const LEN: usize = N;
+
+        /// Returns `v` as a `FixedOpaque`, or [`Error::InvalidLength`] if it
+        /// is not `N` bytes long.
+        pub fn new(v: T) -> Result<Self, Error> {
+            if v.as_ref().len() != N {
+                return Err(Error::InvalidLength);
+            }
+            Ok(Self(v))
+        }
+
+        pub fn get_ref(&self) -> &T {
+            &self.0
+        }
+
+        pub fn into_inner(self) -> T {
+            self.0
+        }
+    }
+
+    impl<T: AsRef<[u8]>, const N: usize> AsRef<[u8]> for FixedOpaque<T, N> {
+        fn as_ref(&self) -> &[u8] {
+            self.0.as_ref()
+        }
+    }
+
+    impl<T: AsRef<[u8]>, const N: usize> Encode for FixedOpaque<T, N> {
+        fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+            buf.write_bytes(self.0.as_ref(), N)
+        }
+    }
+
+    impl<T: WireSize, const N: usize> WireSize for FixedOpaque<T, N> {
+        fn wire_size(&self) -> usize {
+            self.0.wire_size()
+        }
+    }
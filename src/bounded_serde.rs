@@ -0,0 +1,40 @@
+    impl<const N: usize> serde::Serialize for BoundedString<N> {
+        fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_str(self.as_str())
+        }
+    }
+
+    impl<'de, const N: usize> serde::Deserialize<'de> for BoundedString<N> {
+        fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+            let v = <String as serde::Deserialize>::deserialize(d)?;
+            Self::new(v).map_err(serde::de::Error::custom)
+        }
+    }
+
+    impl<T: AsRef<[u8]>, const N: usize> serde::Serialize for BoundedOpaque<T, N> {
+        fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            opaque_serde::serialize(self.get_ref(), s)
+        }
+    }
+
+    impl<'de, T: AsRef<[u8]> + From<Vec<u8>>, const N: usize> serde::Deserialize<'de>
+        for BoundedOpaque<T, N>
+    {
+        fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+            Self::new(opaque_serde::deserialize(d)?).map_err(serde::de::Error::custom)
+        }
+    }
+
+    impl<T: AsRef<[u8]>, const N: usize> serde::Serialize for FixedOpaque<T, N> {
+        fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            opaque_serde::serialize(self.get_ref(), s)
+        }
+    }
+
+    impl<'de, T: AsRef<[u8]> + From<Vec<u8>>, const N: usize> serde::Deserialize<'de>
+        for FixedOpaque<T, N>
+    {
+        fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+            Self::new(opaque_serde::deserialize(d)?).map_err(serde::de::Error::custom)
+        }
+    }
@@ -0,0 +1,242 @@
+    /// The runtime for the generated ONC RPC clients.
+    ///
+    /// Each program version in the spec has a generated client (such as
+    /// `NFS_V3Client`) wrapping an [`RpcClient`], with a method per procedure.
+    /// Messages are sent and received through a [`Transport`].
+    pub mod client {
+        use super::{
+            accept_stat, accepted_reply_data, auth_stat, msg_type, opaque_auth, rejected_reply,
+            reply_body, rpc_msg, rpc_msg_body, Encode, Error, RPC_VERSION,
+        };
+        use fastxdr::bytes::{BufMut, Bytes, BytesMut};
+        use std::convert::TryFrom;
+        use std::future::Future;
+
+        /// `Transport` sends and receives complete ONC RPC messages.
+        ///
+        /// Implementations over a stream transport such as TCP are
+        /// responsible for record marking (see the `record` module).
+        ///
+        /// Replies may be received in any order, and replies that do not
+        /// match the xid of the outstanding call are discarded by the client.
+        pub trait Transport {
+            type Error: std::error::Error + 'static;
+
+            /// Sends a single, encoded call message.
+            fn send(&mut self, msg: Bytes) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+            /// Receives the next encoded reply message.
+            fn recv(&mut self) -> impl Future<Output = Result<Bytes, Self::Error>> + Send;
+        }
+
+        #[derive(Debug)]
+        pub enum ClientError<E>
+        where
+            E: std::error::Error + 'static,
+        {
+            Transport(E),
+            Xdr(Error),
+
+            /// The server does not support version 2 of the RPC protocol.
+            RpcMismatch {
+                low: u32,
+                high: u32,
+            },
+            AuthError(auth_stat),
+            ProgUnavail,
+            ProgMismatch {
+                low: u32,
+                high: u32,
+            },
+            ProcUnavail,
+            GarbageArgs,
+            SystemErr,
+        }
+
+        impl<E> std::fmt::Display for ClientError<E>
+        where
+            E: std::error::Error + 'static,
+        {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Self::Transport(e) => write!(f, "transport error: {}", e),
+                    Self::Xdr(e) => write!(f, "xdr error: {}", e),
+                    Self::RpcMismatch { low, high } => write!(
+                        f,
+                        "rpc version mismatch, server supports versions {} to {}",
+                        low, high
+                    ),
+                    Self::AuthError(stat) => write!(f, "authentication error: {:?}", stat),
+                    Self::ProgUnavail => write!(f, "program unavailable"),
+                    Self::ProgMismatch { low, high } => write!(
+                        f,
+                        "program version mismatch, server supports versions {} to {}",
+                        low, high
+                    ),
+                    Self::ProcUnavail => write!(f, "procedure unavailable"),
+                    Self::GarbageArgs => {
+                        write!(f, "server could not decode the procedure arguments")
+                    }
+                    Self::SystemErr => write!(f, "server system error"),
+                }
+            }
+        }
+
+        impl<E> std::error::Error for ClientError<E>
+        where
+            E: std::error::Error + 'static,
+        {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    Self::Transport(e) => Some(e),
+                    Self::Xdr(e) => Some(e),
+                    _ => None,
+                }
+            }
+        }
+
+        impl<E> From<Error> for ClientError<E>
+        where
+            E: std::error::Error + 'static,
+        {
+            fn from(e: Error) -> Self {
+                Self::Xdr(e)
+            }
+        }
+
+        /// `RpcClient` makes calls to a single version of an RPC program over
+        /// a [`Transport`].
+        ///
+        /// Calls use `AUTH_NONE` credentials unless configured with
+        /// [`RpcClient::set_auth()`].
+        #[derive(Debug)]
+        pub struct RpcClient<R> {
+            transport: R,
+            program: u32,
+            version: u32,
+            xid: u32,
+
+            // The encoded credential and verifier sent with each call.
+            auth: Bytes,
+        }
+
+        impl<R: Transport> RpcClient<R> {
+            pub fn new(transport: R, program: u32, version: u32) -> Self {
+                // Start from a time-derived xid, so a reconnecting client is
+                // unlikely to reuse the xids of its previous calls.
+                let xid = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.subsec_nanos() ^ d.as_secs() as u32)
+                    .unwrap_or_default();
+
+                let mut auth = BytesMut::new();
+                for _ in 0..2 {
+                    // An AUTH_NONE credential or verifier, with an empty body.
+                    auth.put_u32(0);
+                    auth.put_u32(0);
+                }
+
+                Self {
+                    transport,
+                    program,
+                    version,
+                    xid,
+                    auth: auth.freeze(),
+                }
+            }
+
+            /// Sends `cred` and `verf` with all subsequent calls.
+            pub fn set_auth(
+                &mut self,
+                cred: &opaque_auth<Bytes>,
+                verf: &opaque_auth<Bytes>,
+            ) -> Result<(), Error> {
+                let mut auth = BytesMut::new();
+                cred.encode(&mut auth)?;
+                verf.encode(&mut auth)?;
+                self.auth = auth.freeze();
+                Ok(())
+            }
+
+            pub fn get_ref(&self) -> &R {
+                &self.transport
+            }
+
+            pub fn get_mut(&mut self) -> &mut R {
+                &mut self.transport
+            }
+
+            pub fn into_inner(self) -> R {
+                self.transport
+            }
+
+            /// Calls `procedure` with the encoded `args`, returning the
+            /// undecoded procedure result of a successful reply.
+            pub async fn call<A: Encode>(
+                &mut self,
+                procedure: u32,
+                args: &A,
+            ) -> Result<Bytes, ClientError<R::Error>> {
+                self.xid = self.xid.wrapping_add(1);
+                let xid = self.xid;
+
+                let mut buf = BytesMut::new();
+                buf.put_u32(xid);
+                buf.put_u32(msg_type::CALL as u32);
+                buf.put_u32(RPC_VERSION);
+                buf.put_u32(self.program);
+                buf.put_u32(self.version);
+                buf.put_u32(procedure);
+                buf.put_slice(&self.auth);
+                args.encode(&mut buf)?;
+
+                self.transport
+                    .send(buf.freeze())
+                    .await
+                    .map_err(ClientError::Transport)?;
+
+                loop {
+                    let mut reply = self
+                        .transport
+                        .recv()
+                        .await
+                        .map_err(ClientError::Transport)?;
+
+                    let msg = rpc_msg::try_from(&mut reply)?;
+                    if msg.xid != xid {
+                        continue;
+                    }
+
+                    return match msg.body {
+                        rpc_msg_body::REPLY(reply_body::MSG_ACCEPTED(r)) => match r.reply_data {
+                            accepted_reply_data::SUCCESS => Ok(reply),
+                            accepted_reply_data::PROG_MISMATCH(m) => {
+                                Err(ClientError::ProgMismatch {
+                                    low: m.low,
+                                    high: m.high,
+                                })
+                            }
+                            accepted_reply_data::default(stat) => Err(match stat {
+                                accept_stat::PROG_UNAVAIL => ClientError::ProgUnavail,
+                                accept_stat::PROC_UNAVAIL => ClientError::ProcUnavail,
+                                accept_stat::GARBAGE_ARGS => ClientError::GarbageArgs,
+                                _ => ClientError::SystemErr,
+                            }),
+                        },
+                        rpc_msg_body::REPLY(reply_body::MSG_DENIED(r)) => Err(match r {
+                            rejected_reply::RPC_MISMATCH(m) => ClientError::RpcMismatch {
+                                low: m.low,
+                                high: m.high,
+                            },
+                            rejected_reply::AUTH_ERROR(stat) => ClientError::AuthError(stat),
+                        }),
+                        rpc_msg_body::CALL(_) => Err(Error::Unknown(format!(
+                            "received call message with xid {}, expected a reply",
+                            xid
+                        ))
+                        .into()),
+                    };
+                }
+            }
+        }
+    }
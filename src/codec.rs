@@ -0,0 +1,115 @@
+    /// A [`tokio_util::codec`] implementation for streams of XDR messages
+    /// framed with ONC RPC record marking.
+    ///
+    /// [`tokio_util::codec`]: https://docs.rs/tokio-util/latest/tokio_util/codec/
+    pub mod codec {
+        use super::{record, Encode, Error};
+        use fastxdr::bytes::{Bytes, BytesMut};
+        use std::convert::TryFrom;
+        use std::marker::PhantomData;
+
+        #[derive(Debug)]
+        pub enum CodecError {
+            Io(std::io::Error),
+            Xdr(Error),
+        }
+
+        impl std::fmt::Display for CodecError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Self::Io(e) => write!(f, "i/o error: {}", e),
+                    Self::Xdr(e) => write!(f, "xdr error: {}", e),
+                }
+            }
+        }
+
+        impl std::error::Error for CodecError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    Self::Io(e) => Some(e),
+                    Self::Xdr(e) => Some(e),
+                }
+            }
+        }
+
+        impl From<std::io::Error> for CodecError {
+            fn from(e: std::io::Error) -> Self {
+                Self::Io(e)
+            }
+        }
+
+        impl From<Error> for CodecError {
+            fn from(e: Error) -> Self {
+                Self::Xdr(e)
+            }
+        }
+
+        /// `XdrCodec` decodes each record in a stream as a `T`, and encodes
+        /// any [`Encode`] type as a single-fragment record.
+        ///
+        /// Use it with a `Framed` transport to read and write typed messages:
+        ///
+        /// ```ignore
+        /// let mut framed = Framed::new(stream, XdrCodec::<CallType>::new());
+        /// ```
+        #[derive(Debug)]
+        pub struct XdrCodec<T> {
+            max_record_len: Option<usize>,
+            _type: PhantomData<fn() -> T>,
+        }
+
+        impl<T> XdrCodec<T> {
+            pub fn new() -> Self {
+                Self {
+                    max_record_len: None,
+                    _type: PhantomData,
+                }
+            }
+
+            /// Rejects records with a payload larger than `max` bytes, rather
+            /// than buffering them.
+            pub fn with_max_record_len(max: usize) -> Self {
+                Self {
+                    max_record_len: Some(max),
+                    _type: PhantomData,
+                }
+            }
+        }
+
+        impl<T> Default for XdrCodec<T> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T> tokio_util::codec::Decoder for XdrCodec<T>
+        where
+            T: TryFrom<Bytes, Error = Error>,
+        {
+            type Item = T;
+            type Error = CodecError;
+
+            fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, CodecError> {
+                match record::read_record(src, self.max_record_len) {
+                    Ok(b) => Ok(Some(T::try_from(b)?)),
+                    Err(Error::Incomplete { needed }) => {
+                        src.reserve(needed);
+                        Ok(None)
+                    }
+                    Err(e) => Err(e.into()),
+                }
+            }
+        }
+
+        impl<T, I> tokio_util::codec::Encoder<I> for XdrCodec<T>
+        where
+            I: Encode,
+        {
+            type Error = CodecError;
+
+            fn encode(&mut self, item: I, dst: &mut BytesMut) -> Result<(), CodecError> {
+                record::encode_record(&item, dst)?;
+                Ok(())
+            }
+        }
+    }
@@ -0,0 +1,530 @@
+use crate::ast::Rule;
+use thiserror::Error;
+
+/// A line and column position in an XDR spec, both starting from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// Returns the `Span` of the byte `offset` into `source`.
+    pub(crate) fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = match before.rfind('\n') {
+            Some(i) => before[i + 1..].chars().count() + 1,
+            None => before.chars().count() + 1,
+        };
+
+        Span { line, column }
+    }
+
+    /// Renders the source line containing this span, with a marker pointing at
+    /// the column.
+    pub(crate) fn snippet(&self, source: &str) -> String {
+        let line = source.lines().nth(self.line - 1).unwrap_or("");
+        let gutter = " ".repeat(self.line.to_string().len());
+
+        // Preserve any tabs in the source line so the marker lines up.
+        let indent = line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        format!(
+            "{0} |\n{1} | {2}\n{0} | {3}^",
+            gutter, self.line, line, indent
+        )
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Errors returned when parsing an XDR spec or generating code from it.
+///
+/// Errors caused by the spec itself carry the [`Span`] of the offending
+/// source, and a rendered snippet of the line it occurs on.
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("syntax error at {span}: {message}\n{snippet}")]
+    Syntax {
+        message: String,
+        span: Span,
+        snippet: String,
+    },
+
+    #[error("unknown type {name} at {span}\n{snippet}")]
+    UnknownType {
+        name: String,
+        span: Span,
+        snippet: String,
+    },
+
+    #[error("unknown constant {name} at {span}\n{snippet}")]
+    UnknownConstant {
+        name: String,
+        span: Span,
+        snippet: String,
+    },
+
+    #[error("recursive type {name} has infinite size at {span}\n{snippet}")]
+    RecursiveType {
+        name: String,
+        span: Span,
+        snippet: String,
+    },
+
+    #[error("duplicate definition of {name} at {span}\n{snippet}")]
+    DuplicateDefinition {
+        name: String,
+        span: Span,
+        snippet: String,
+    },
+
+    #[error("invalid array size {size} at {span}\n{snippet}")]
+    InvalidArraySize {
+        size: String,
+        span: Span,
+        snippet: String,
+    },
+
+    /// A definition the generator cannot generate code for, such as an
+    /// unsupported type or an out of range value.
+    #[error("invalid definition at {span}: {message}\n{snippet}")]
+    InvalidDefinition {
+        message: String,
+        span: Span,
+        snippet: String,
+    },
+
+    #[error("{0}")]
+    Generate(String),
+
+    /// An error in one of several spec files, naming the file it occurs in.
+    #[error("{file}: {error}")]
+    InFile { file: String, error: Box<Error> },
+}
+
+impl Error {
+    /// Returns the location of the error in the XDR source, if known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::Syntax { span, .. }
+            | Error::UnknownType { span, .. }
+            | Error::UnknownConstant { span, .. }
+            | Error::RecursiveType { span, .. }
+            | Error::DuplicateDefinition { span, .. }
+            | Error::InvalidArraySize { span, .. }
+            | Error::InvalidDefinition { span, .. } => Some(*span),
+            Error::Generate(_) => None,
+            Error::InFile { error, .. } => error.span(),
+        }
+    }
+
+    /// Returns the name of the spec file the error occurs in, if it was read
+    /// from one of several files.
+    pub fn file(&self) -> Option<&str> {
+        match self {
+            Error::InFile { file, .. } => Some(file.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Attributes the error to the spec file `file`.
+    pub(crate) fn in_file<T: Into<String>>(self, file: T) -> Self {
+        match self {
+            e @ Error::InFile { .. } => e,
+            e => Error::InFile {
+                file: file.into(),
+                error: Box::new(e),
+            },
+        }
+    }
+
+    pub(crate) fn syntax<T: Into<String>>(source: &str, offset: usize, message: T) -> Self {
+        let span = Span::from_offset(source, offset);
+        Error::Syntax {
+            message: message.into(),
+            snippet: span.snippet(source),
+            span,
+        }
+    }
+
+    pub(crate) fn unknown_type<T: Into<String>>(source: &str, offset: usize, name: T) -> Self {
+        let span = Span::from_offset(source, offset);
+        Error::UnknownType {
+            name: name.into(),
+            snippet: span.snippet(source),
+            span,
+        }
+    }
+
+    pub(crate) fn unknown_constant<T: Into<String>>(source: &str, offset: usize, name: T) -> Self {
+        let span = Span::from_offset(source, offset);
+        Error::UnknownConstant {
+            name: name.into(),
+            snippet: span.snippet(source),
+            span,
+        }
+    }
+
+    pub(crate) fn recursive_type<T: Into<String>>(source: &str, offset: usize, name: T) -> Self {
+        let span = Span::from_offset(source, offset);
+        Error::RecursiveType {
+            name: name.into(),
+            snippet: span.snippet(source),
+            span,
+        }
+    }
+
+    pub(crate) fn duplicate<T: Into<String>>(source: &str, offset: usize, name: T) -> Self {
+        let span = Span::from_offset(source, offset);
+        Error::DuplicateDefinition {
+            name: name.into(),
+            snippet: span.snippet(source),
+            span,
+        }
+    }
+
+    pub(crate) fn array_size<T: Into<String>>(source: &str, offset: usize, size: T) -> Self {
+        let span = Span::from_offset(source, offset);
+        Error::InvalidArraySize {
+            size: size.into(),
+            snippet: span.snippet(source),
+            span,
+        }
+    }
+
+    pub(crate) fn invalid<T: Into<String>>(source: &str, offset: usize, message: T) -> Self {
+        let span = Span::from_offset(source, offset);
+        Error::InvalidDefinition {
+            message: message.into(),
+            snippet: span.snippet(source),
+            span,
+        }
+    }
+
+    /// Attributes an error raised without source context to the definition at
+    /// `span`.
+    ///
+    /// Errors that already carry a span are returned unchanged.
+    pub(crate) fn with_span(self, span: pest::Span<'_>) -> Self {
+        match self {
+            Error::Generate(message) => Error::invalid(span.get_input(), span.start(), message),
+            e => e,
+        }
+    }
+
+    pub(crate) fn from_pest(source: &str, e: pest::error::Error<Rule>) -> Self {
+        let (line, column) = match e.line_col {
+            pest::error::LineColLocation::Pos(p) => p,
+            pest::error::LineColLocation::Span(p, _) => p,
+        };
+
+        let span = Span { line, column };
+        Error::Syntax {
+            message: e.variant.message().to_string(),
+            snippet: span.snippet(source),
+            span,
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(v: String) -> Self {
+        Error::Generate(v)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(v: &str) -> Self {
+        Error::Generate(v.to_string())
+    }
+}
+
+impl From<std::fmt::Error> for Error {
+    fn from(v: std::fmt::Error) -> Self {
+        Error::Generate(v.to_string())
+    }
+}
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(v: std::num::ParseIntError) -> Self {
+        Error::Generate(v.to_string())
+    }
+}
+
+/// Returns the byte offset of the first occurrence of the identifier `name`
+/// within `source[start..end]`, or `start` if it cannot be found.
+pub(crate) fn find_ident(source: &str, start: usize, end: usize, name: &str) -> usize {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let haystack = &source[start..end];
+
+    haystack
+        .match_indices(name)
+        .find(|(i, _)| {
+            let before = haystack[..*i].chars().next_back();
+            let after = haystack[i + name.len()..].chars().next();
+            !before.map(is_ident).unwrap_or(false) && !after.map(is_ident).unwrap_or(false)
+        })
+        .map(|(i, _)| start + i)
+        .unwrap_or(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Ast;
+
+    #[test]
+    fn test_span_from_offset() {
+        let source = "first\nsecond line\n\tthird";
+
+        assert_eq!(Span::from_offset(source, 0), Span { line: 1, column: 1 });
+        assert_eq!(Span::from_offset(source, 3), Span { line: 1, column: 4 });
+        assert_eq!(Span::from_offset(source, 6), Span { line: 2, column: 1 });
+        assert_eq!(Span::from_offset(source, 13), Span { line: 2, column: 8 });
+        assert_eq!(Span::from_offset(source, 19), Span { line: 3, column: 2 });
+    }
+
+    #[test]
+    fn test_snippet() {
+        let source = "first\n\tsecond line";
+        let span = Span { line: 2, column: 9 };
+
+        assert_eq!(
+            span.snippet(source),
+            "  |\n2 | \tsecond line\n  | \t       ^"
+        );
+    }
+
+    #[test]
+    fn test_find_ident() {
+        let source = "typedef news new;";
+
+        assert_eq!(find_ident(source, 0, source.len(), "new"), 13);
+        assert_eq!(find_ident(source, 0, source.len(), "missing"), 0);
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let input = "const A = 1;\nstruct s {\n\tuint32_t a\n};";
+
+        match Ast::new(input).unwrap_err() {
+            Error::Syntax { span, snippet, .. } => {
+                assert_eq!(span, Span { line: 4, column: 1 });
+                assert_eq!(snippet, "  |\n4 | };\n  | ^");
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_type() {
+        let input = r#"struct thing {
+    uint32_t a;
+};
+typedef uint32_t thing;"#;
+
+        match Ast::new(input).unwrap_err() {
+            Error::DuplicateDefinition { name, span, .. } => {
+                assert_eq!(name, "thing");
+                assert_eq!(
+                    span,
+                    Span {
+                        line: 4,
+                        column: 18
+                    }
+                );
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_constant() {
+        let input = r#"const OK = 1;
+enum status {
+    OK = 0
+};"#;
+
+        match Ast::new(input).unwrap_err() {
+            Error::DuplicateDefinition { name, span, .. } => {
+                assert_eq!(name, "OK");
+                assert_eq!(span, Span { line: 3, column: 5 });
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_invalid_array_size() {
+        let input = r#"
+struct thing {
+    uint32_t a<99999999999>;
+};"#;
+
+        match Ast::new(input).unwrap_err() {
+            Error::InvalidArraySize { size, span, .. } => {
+                assert_eq!(size, "99999999999");
+                assert_eq!(
+                    span,
+                    Span {
+                        line: 3,
+                        column: 16
+                    }
+                );
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        let input = "union thing switch (missing t) {\n    case 1:\n        void;\n};";
+
+        match crate::Generator::default().generate(input).unwrap_err() {
+            Error::UnknownType { name, span, .. } => {
+                assert_eq!(name, "missing");
+                assert_eq!(
+                    span,
+                    Span {
+                        line: 1,
+                        column: 21
+                    }
+                );
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_fixed_length_string() {
+        let input = "struct s { string x[4]; };";
+
+        match crate::Generator::default().generate(input).unwrap_err() {
+            Error::InvalidDefinition { message, span, .. } => {
+                assert_eq!(message, "string x cannot have a fixed length");
+                assert_eq!(
+                    span,
+                    Span {
+                        line: 1,
+                        column: 19
+                    }
+                );
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_invalid_union_switch() {
+        let input = "union thing switch (float d) {\n    case 1:\n        void;\n};";
+
+        match crate::Generator::default().generate(input).unwrap_err() {
+            Error::InvalidDefinition { message, span, .. } => {
+                assert_eq!(message, "union switch d must be an integer, bool or enum");
+                assert_eq!(
+                    span,
+                    Span {
+                        line: 1,
+                        column: 27
+                    }
+                );
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_invalid_literal() {
+        let input = "enum e {\n    A = 1,\n    B = 0x\n};";
+
+        match crate::Generator::default().generate(input).unwrap_err() {
+            Error::InvalidDefinition { message, span, .. } => {
+                assert_eq!(message, "invalid integer constant 0x");
+                assert_eq!(span, Span { line: 3, column: 9 });
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = Error::duplicate("const A = 1;", 6, "A");
+        assert_eq!(
+            err.to_string(),
+            "duplicate definition of A at line 1, column 7\n  |\n1 | const A = 1;\n  |       ^"
+        );
+    }
+
+    #[test]
+    fn test_invalid_definition() {
+        let input = "const A = 1;\nenum flags {\n    ALL = 0xFFFFFFFFF\n};";
+
+        let err = Ast::new(input).unwrap_err();
+        assert!(matches!(err, Error::InvalidDefinition { .. }));
+        assert_eq!(
+            err.to_string(),
+            "invalid definition at line 2, column 1: enum value 0xFFFFFFFFF does not fit in an i32\n  |\n2 | enum flags {\n  | ^"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_across_files() {
+        let files = [
+            ("base.x", "struct thing {\n    uint32_t a;\n};"),
+            ("ops.x", "const A = 1;\ntypedef uint32_t thing;"),
+        ];
+
+        let err = Ast::from_files(&files).unwrap_err();
+        assert_eq!(err.file(), Some("ops.x"));
+        assert_eq!(
+            err.span(),
+            Some(Span {
+                line: 2,
+                column: 18
+            })
+        );
+        assert!(err
+            .to_string()
+            .starts_with("ops.x: duplicate definition of thing at line 2, column 18\n"));
+    }
+
+    #[test]
+    fn test_syntax_error_in_file() {
+        let files = [
+            ("base.x", "const A = 1;"),
+            ("ops.x", "struct s {\n};\nstruct"),
+        ];
+
+        match Ast::from_files(&files).unwrap_err() {
+            Error::InFile { file, error } => {
+                assert_eq!(file, "ops.x");
+                assert_eq!(error.span(), Some(Span { line: 3, column: 7 }));
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_unknown_type_in_file() {
+        let files = [
+            ("base.x", "struct thing { uint32_t a; };"),
+            ("ops.x", "struct other {\n    thing a;\n    missing b;\n};"),
+        ];
+
+        let err = crate::Generator::default()
+            .generate_files(&files)
+            .unwrap_err();
+        assert_eq!(err.file(), Some("ops.x"));
+        assert_eq!(err.span(), Some(Span { line: 3, column: 5 }));
+    }
+}
@@ -0,0 +1,592 @@
+use super::{BoundedType, Message};
+use crate::ast::{indexes::*, ArraySize, ArrayType, Ast, BasicType};
+use crate::Result;
+use std::collections::{BTreeSet, HashMap};
+
+/// The kind of a generated type, used to select the types an attribute rule
+/// applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeKind {
+    Struct,
+    /// A discriminated union, generated as a Rust enum.
+    Union,
+    Enum,
+    /// A typedef, generated as a newtype struct.
+    Typedef,
+    /// The `Call` and `Reply` enums generated for each RPC program version.
+    Message,
+}
+
+/// Selects the generated types an attribute rule applies to.
+///
+/// Strings convert to a [`TypeMatch::Name`] pattern, and a [`TypeKind`] to a
+/// [`TypeMatch::Kind`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeMatch {
+    /// Types with a name matching the glob pattern, where `*` matches any
+    /// sequence of characters and `?` matches any single character.
+    Name(String),
+    /// All types of the given kind.
+    Kind(TypeKind),
+}
+
+impl TypeMatch {
+    fn matches(&self, name: &str, kind: TypeKind) -> bool {
+        match self {
+            Self::Name(pattern) => glob_match(pattern, name),
+            Self::Kind(k) => *k == kind,
+        }
+    }
+}
+
+impl From<&str> for TypeMatch {
+    fn from(v: &str) -> Self {
+        Self::Name(v.to_string())
+    }
+}
+
+impl From<String> for TypeMatch {
+    fn from(v: String) -> Self {
+        Self::Name(v)
+    }
+}
+
+impl From<TypeKind> for TypeMatch {
+    fn from(v: TypeKind) -> Self {
+        Self::Kind(v)
+    }
+}
+
+/// The string encoding of opaque data when generating serde implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpaqueFormat {
+    /// Standard base64 with padding, as in RFC 4648.
+    Base64,
+    /// Lowercase hexadecimal.
+    Hex,
+}
+
+impl OpaqueFormat {
+    /// Returns the name of the runtime module serialising opaque data in this
+    /// format.
+    pub(crate) fn module(&self) -> &'static str {
+        match self {
+            Self::Base64 => "opaque_base64",
+            Self::Hex => "opaque_hex",
+        }
+    }
+}
+
+/// The attributes prepended to the generated types, and to their fields and
+/// variants.
+///
+/// Derived traits that a type cannot implement, such as `Eq` for a type
+/// containing an `f32`, are omitted from its derive line.
+#[derive(Debug, Clone)]
+pub struct Attributes {
+    derive: String,
+    type_derives: Vec<(TypeMatch, String)>,
+    type_attributes: Vec<(TypeMatch, String)>,
+    field_attributes: Vec<(String, String)>,
+    serde: Option<OpaqueFormat>,
+}
+
+impl Attributes {
+    /// Returns the attributes prepending the `derive` line to every type.
+    pub fn new<D: AsRef<str>>(derive: D) -> Self {
+        Self {
+            derive: derive.as_ref().to_string(),
+            type_derives: Vec::new(),
+            type_attributes: Vec::new(),
+            field_attributes: Vec::new(),
+            serde: None,
+        }
+    }
+
+    /// Replaces the derive line prepended to every type.
+    pub fn with_derive<D: AsRef<str>>(self, derive: D) -> Self {
+        Self {
+            derive: derive.as_ref().to_string(),
+            ..self
+        }
+    }
+
+    /// Derives the comma separated `traits` for the types selected by `m`, in
+    /// addition to the derive line.
+    pub fn with_type_derive<M: Into<TypeMatch>, D: AsRef<str>>(mut self, m: M, traits: D) -> Self {
+        self.type_derives
+            .push((m.into(), traits.as_ref().to_string()));
+        self
+    }
+
+    /// Prepends `attribute` to the types selected by `m`.
+    pub fn with_type_attribute<M: Into<TypeMatch>, A: AsRef<str>>(
+        mut self,
+        m: M,
+        attribute: A,
+    ) -> Self {
+        self.type_attributes
+            .push((m.into(), attribute.as_ref().to_string()));
+        self
+    }
+
+    /// Prepends `attribute` to the struct fields, union and enum variants, and
+    /// message variants matching `path`.
+    ///
+    /// The path is of the form `type.field`, using the names in the XDR spec,
+    /// and each half is a glob pattern as in [`TypeMatch::Name`]. A path
+    /// without a `.` matches the field in any type.
+    pub fn with_field_attribute<P: AsRef<str>, A: AsRef<str>>(
+        mut self,
+        path: P,
+        attribute: A,
+    ) -> Self {
+        self.field_attributes
+            .push((path.as_ref().to_string(), attribute.as_ref().to_string()));
+        self
+    }
+
+    /// Derives `serde::Serialize` and `serde::Deserialize` for every type,
+    /// serialising opaque data as a string in `format`.
+    pub fn with_serde(self, format: OpaqueFormat) -> Self {
+        Self {
+            serde: Some(format),
+            ..self
+        }
+    }
+
+    /// Returns the encoding of opaque data if serde implementations are
+    /// generated.
+    pub(crate) fn serde(&self) -> Option<OpaqueFormat> {
+        self.serde
+    }
+
+    /// Returns the traits added by the type derive rules selecting the type
+    /// `name` of `kind`, or by any rule if `None`, excluding those in the
+    /// derive line.
+    fn rule_traits(&self, selected: Option<(&str, TypeKind)>) -> BTreeSet<&str> {
+        let base = split_derive(&self.derive).unwrap_or_default();
+
+        self.type_derives
+            .iter()
+            .filter(|(m, _)| selected.is_none_or(|(name, kind)| m.matches(name, kind)))
+            .flat_map(|(_, traits)| split_traits(traits))
+            .map(trait_name)
+            .filter(|t| !base.iter().any(|b| trait_name(b) == *t))
+            .collect()
+    }
+
+    /// Prints the derive line and attributes of the type `name`, which is
+    /// `generic` over the opaque type `T`.
+    pub(crate) fn print_type<W: std::fmt::Write>(
+        &self,
+        w: &mut W,
+        name: &str,
+        kind: TypeKind,
+        generic: bool,
+        derivable: &Derivable,
+    ) -> Result<()> {
+        let serde = match self.serde {
+            Some(_) => "serde::Serialize, serde::Deserialize",
+            None => "",
+        };
+        let extra = self
+            .type_derives
+            .iter()
+            .filter(|(m, _)| m.matches(name, kind))
+            .flat_map(|(_, traits)| split_traits(traits))
+            .chain(split_traits(serde));
+
+        let mut traits = Vec::new();
+        match split_derive(&self.derive) {
+            Some(base) => traits.extend(base),
+            None => writeln!(w, "{}", self.derive)?,
+        }
+        for t in extra {
+            if !traits.contains(&t) {
+                traits.push(t);
+            }
+        }
+
+        traits.retain(|t| derivable.allows(name, t));
+        if !traits.is_empty() {
+            writeln!(w, "#[derive({})]", traits.join(", "))?;
+        }
+
+        // The opaque type T is serialised by the opaque_serde module rather
+        // than its own implementation, so replace the inferred T: Serialize
+        // bounds with those of the module.
+        if self.serde.is_some() && generic {
+            writeln!(
+                w,
+                r#"#[serde(bound(serialize = "T: AsRef<[u8]>", deserialize = "T: From<Vec<u8>>"))]"#
+            )?;
+        }
+
+        for (_, a) in self
+            .type_attributes
+            .iter()
+            .filter(|(m, _)| m.matches(name, kind))
+        {
+            writeln!(w, "{}", a)?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints the attributes of the field or variant `field` of the type
+    /// `name`, generated as `ident`.
+    ///
+    /// When generating serde implementations, fields keep their XDR name, and
+    /// `opaque` fields of type `T` are serialised as strings.
+    pub(crate) fn print_field<W: std::fmt::Write>(
+        &self,
+        w: &mut W,
+        name: &str,
+        field: &str,
+        ident: &str,
+        opaque: bool,
+    ) -> Result<()> {
+        if self.serde.is_some() {
+            if ident != field {
+                writeln!(w, "#[serde(rename = \"{}\")]", field)?;
+            }
+            if opaque {
+                writeln!(w, "{}", SERDE_OPAQUE)?;
+            }
+        }
+
+        for (path, a) in self.field_attributes.iter() {
+            let matched = match path.split_once('.') {
+                Some((t, f)) => glob_match(t, name) && glob_match(f, field),
+                None => glob_match(path, field),
+            };
+            if matched {
+                writeln!(w, "{}", a)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints the serde attribute of an opaque value of type `T` in a tuple
+    /// struct or variant, if serde implementations are generated.
+    pub(crate) fn print_opaque<W: std::fmt::Write>(&self, w: &mut W) -> Result<()> {
+        if self.serde.is_some() {
+            write!(w, "{} ", SERDE_OPAQUE)?;
+        }
+        Ok(())
+    }
+}
+
+const SERDE_OPAQUE: &str = r#"#[serde(with = "opaque_serde")]"#;
+
+/// Splits a `#[derive(...)]` line into its traits, returning `None` for any
+/// other attribute.
+fn split_derive(line: &str) -> Option<Vec<&str>> {
+    let inner = line.trim().strip_prefix("#[derive(")?.strip_suffix(")]")?;
+
+    match inner.contains(['(', ')', '[', ']']) {
+        true => None,
+        false => Some(split_traits(inner).collect()),
+    }
+}
+
+fn split_traits(traits: &str) -> impl Iterator<Item = &str> {
+    traits.split(',').map(str::trim).filter(|t| !t.is_empty())
+}
+
+/// Returns the name of the trait `t`, which may be a path.
+fn trait_name(t: &str) -> &str {
+    t.rsplit("::").next().unwrap_or(t).trim()
+}
+
+/// Returns true if `name` matches the glob `pattern`.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    fn matches(p: &[char], n: &[char]) -> bool {
+        match p.split_first() {
+            None => n.is_empty(),
+            Some(('*', rest)) => (0..=n.len()).any(|i| matches(rest, &n[i..])),
+            Some(('?', rest)) => !n.is_empty() && matches(rest, &n[1..]),
+            Some((c, rest)) => n.first() == Some(c) && matches(rest, &n[1..]),
+        }
+    }
+
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    matches(&p, &n)
+}
+
+/// The derivable standard library traits that each generated type cannot
+/// implement.
+///
+/// Floats are not `Eq`, `Hash` or `Ord`, heap allocated values are not `Copy`,
+/// and enums, unions and arrays of more than 32 elements have no `Default`.
+/// Traits added by a type derive rule are not implemented by the types the
+/// rule does not select. Each type inherits the traits of the types it
+/// contains.
+#[derive(Debug, Default)]
+pub(crate) struct Derivable<'a>(HashMap<String, BTreeSet<&'a str>>);
+
+impl<'a> Derivable<'a> {
+    pub(crate) fn new(ast: &Ast, attributes: &'a Attributes, bounded: bool) -> Self {
+        // The traits each type cannot derive, ignoring the types it contains,
+        // and the names of the types it contains.
+        let mut direct = Vec::new();
+
+        for item in ast.types().iter() {
+            let mut blocked = BTreeSet::new();
+            let mut refs = Vec::new();
+
+            let (name, kind) = match item {
+                AstType::Struct(v) => {
+                    for f in v.fields.iter() {
+                        if f.is_optional {
+                            blocked.insert("Copy");
+                        }
+                        member(&f.field_value, bounded, &mut blocked, &mut refs);
+                    }
+                    (v.name.clone(), TypeKind::Struct)
+                }
+                AstType::Union(v) => {
+                    blocked.insert("Default");
+                    for c in v.cases.iter().chain(v.default.iter()) {
+                        member(&c.field_value, bounded, &mut blocked, &mut refs);
+                    }
+                    (v.name.clone(), TypeKind::Union)
+                }
+                AstType::Enum(v) => {
+                    blocked.insert("Default");
+                    (v.name.clone(), TypeKind::Enum)
+                }
+                AstType::Typedef(v) => {
+                    member(&v.target_array(), bounded, &mut blocked, &mut refs);
+                    (
+                        v.alias.unwrap_array().as_str().to_string(),
+                        TypeKind::Typedef,
+                    )
+                }
+            };
+
+            direct.push((name, kind, blocked, refs));
+        }
+
+        for program in ast.programs().iter() {
+            for version in program.versions.iter() {
+                for msg in Message::all().iter() {
+                    let mut blocked = BTreeSet::new();
+                    let mut refs = Vec::new();
+
+                    blocked.insert("Default");
+                    for t in version.procedures.iter().flat_map(|p| msg.types(p)) {
+                        member(
+                            &ArrayType::None(t.clone()),
+                            bounded,
+                            &mut blocked,
+                            &mut refs,
+                        );
+                    }
+
+                    direct.push((msg.type_name(version), TypeKind::Message, blocked, refs));
+                }
+            }
+        }
+
+        // Block the traits added by the type derive rules for the types they
+        // do not select, so the types containing those omit them too.
+        let rule_traits = attributes.rule_traits(None);
+        for (name, kind, blocked, _) in direct.iter_mut() {
+            let selected = attributes.rule_traits(Some((name.as_str(), *kind)));
+            blocked.extend(rule_traits.difference(&selected).copied());
+        }
+
+        // Propagate the traits of each contained type until no type changes,
+        // as types may contain themselves.
+        let mut index: HashMap<String, BTreeSet<&'a str>> = direct
+            .iter()
+            .map(|(name, _, blocked, _)| (name.clone(), blocked.clone()))
+            .collect();
+
+        loop {
+            let mut changed = false;
+            for (name, _, _, refs) in direct.iter() {
+                let inherited: Vec<&'a str> = refs
+                    .iter()
+                    .filter_map(|r| index.get(r.as_str()))
+                    .flatten()
+                    .copied()
+                    .collect();
+
+                if let Some(blocked) = index.get_mut(name) {
+                    for t in inherited {
+                        changed |= blocked.insert(t);
+                    }
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        Self(index)
+    }
+
+    /// Returns true if the type `name` can derive the trait `t`, which may be
+    /// a path.
+    pub(crate) fn allows(&self, name: &str, t: &str) -> bool {
+        self.0
+            .get(name)
+            .is_none_or(|blocked| !blocked.contains(trait_name(t)))
+    }
+}
+
+/// Records the traits a member of type `t` prevents its type from deriving,
+/// and the name of the type it contains, if any.
+fn member(
+    t: &ArrayType<BasicType>,
+    bounded: bool,
+    blocked: &mut BTreeSet<&'static str>,
+    refs: &mut Vec<String>,
+) {
+    match (t, t.unwrap_array()) {
+        // Unbounded opaques are the generic type T, for which the derive adds
+        // the trait bounds.
+        (_, BasicType::Opaque) => match BoundedType::new(t).filter(|_| bounded) {
+            Some(b) if b.name == "FixedOpaque" => {
+                blocked.insert("Copy");
+                blocked.insert("Default");
+            }
+            Some(_) => {
+                blocked.insert("Copy");
+            }
+            None => (),
+        },
+        (_, BasicType::String) | (ArrayType::VariableSize(..), _) => {
+            blocked.insert("Copy");
+        }
+        (ArrayType::FixedSize(_, s), _) if !matches!(s, ArraySize::Known(n) if *n <= 32) => {
+            blocked.insert("Default");
+        }
+        _ => (),
+    }
+
+    match t.unwrap_array() {
+        BasicType::F32 | BasicType::F64 => {
+            blocked.extend(["Eq", "Hash", "Ord"]);
+        }
+        BasicType::Ident(i) => refs.push(i.clone()),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("nfs_fh3", "nfs_fh3"));
+        assert!(glob_match("nfs_*", "nfs_fh3"));
+        assert!(glob_match("*3", "nfs_fh3"));
+        assert!(glob_match("nfs_fh?", "nfs_fh3"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("nfs_*", "fh3"));
+        assert!(!glob_match("nfs_fh?", "nfs_fh"));
+        assert!(!glob_match("nfs", "nfs_fh3"));
+    }
+
+    #[test]
+    fn test_split_derive() {
+        assert_eq!(
+            split_derive("#[derive(Debug, PartialEq)]"),
+            Some(vec!["Debug", "PartialEq"])
+        );
+        assert_eq!(split_derive("#[derive()]"), Some(vec![]));
+        assert_eq!(split_derive("#[serde(rename = \"a\")]"), None);
+        assert_eq!(split_derive("#[derive(Debug)]\n#[serde(default)]"), None);
+    }
+
+    #[test]
+    fn test_derivable() {
+        let ast = Ast::new(
+            r#"
+			struct point {
+				float x;
+				float y;
+			};
+			struct shape {
+				point points<>;
+				shape *next;
+			};
+			struct fixed {
+				int values[64];
+			};
+			enum kind {
+				A = 1
+			};
+			typedef kind kinds[2];
+		"#,
+        )
+        .unwrap();
+
+        let attributes = Attributes::new("");
+        let d = Derivable::new(&ast, &attributes, false);
+
+        assert!(d.allows("point", "Copy"));
+        assert!(d.allows("point", "PartialEq"));
+        assert!(!d.allows("point", "Eq"));
+        assert!(!d.allows("point", "std::hash::Hash"));
+
+        assert!(!d.allows("shape", "Eq"));
+        assert!(!d.allows("shape", "Copy"));
+        assert!(d.allows("shape", "Default"));
+
+        assert!(!d.allows("fixed", "Default"));
+        assert!(d.allows("fixed", "Eq"));
+
+        assert!(!d.allows("kind", "Default"));
+        assert!(!d.allows("kinds", "Default"));
+        assert!(d.allows("kinds", "Copy"));
+
+        assert!(d.allows("unknown", "Eq"));
+    }
+
+    #[test]
+    fn test_derivable_rules() {
+        let ast = Ast::new(
+            r#"
+			typedef opaque cookie[8];
+			typedef string filename<>;
+			struct entry {
+				cookie c;
+				filename name;
+			};
+			struct listing {
+				entry *entries;
+			};
+			struct point {
+				int x;
+			};
+		"#,
+        )
+        .unwrap();
+
+        let attributes = Attributes::new("#[derive(Debug, Clone)]")
+            .with_type_derive(TypeKind::Struct, "Clone, Eq, std::hash::Hash")
+            .with_type_derive("cookie", "Eq");
+        let d = Derivable::new(&ast, &attributes, false);
+
+        // The typedefs are not selected by the struct rule, so the structs
+        // containing them cannot derive its traits.
+        assert!(d.allows("entry", "Clone"));
+        assert!(!d.allows("entry", "Eq"));
+        assert!(!d.allows("entry", "Hash"));
+        assert!(!d.allows("listing", "Hash"));
+        assert!(!d.allows("listing", "Eq"));
+
+        assert!(d.allows("cookie", "Eq"));
+        assert!(d.allows("point", "Eq"));
+        assert!(d.allows("point", "Hash"));
+    }
+}
@@ -0,0 +1,214 @@
+use super::from::{print_decode_array, TypeResolve};
+use super::procedures::{method_name, print_message_type, resolve_number, Message};
+use super::SafeName;
+use crate::ast::{ArrayType, Ast};
+use crate::impls::template::*;
+use crate::Result;
+
+/// Prints a client for each program version, with an async method per
+/// procedure.
+///
+/// Each method encodes the arguments as a `Call` message for the procedure,
+/// sends it using the `client::RpcClient` runtime and decodes the typed result
+/// from the reply.
+pub fn print_clients<W: std::fmt::Write, T: FromTemplate>(
+    mut w: W,
+    template: T,
+    ast: &Ast,
+) -> Result<()> {
+    for program in ast.emitted_programs() {
+        let program_number = resolve_number(&program.number, ast)?;
+
+        for version in program.versions.iter() {
+            let name = format!("{}Client", version.name());
+            let version_number = resolve_number(&version.number, ast)?;
+
+            let call = Message::Call.type_path(version, ast, template);
+
+            writeln!(
+                w,
+                "/// A client for version {} of the {} program.",
+                version_number,
+                program.name()
+            )?;
+            writeln!(w, "#[derive(Debug)]")?;
+            writeln!(w, "pub struct {}<R> {{", name)?;
+            writeln!(w, "rpc: client::RpcClient<R>,")?;
+            writeln!(w, "}}")?;
+
+            writeln!(w, "impl<R: client::Transport> {}<R> {{", name)?;
+            writeln!(w, "pub const PROGRAM: u32 = {};", program_number)?;
+            writeln!(w, "pub const VERSION: u32 = {};", version_number)?;
+            writeln!(
+                w,
+                "pub fn new(transport: R) -> Self {{\nSelf {{ rpc: client::RpcClient::new(transport, Self::PROGRAM, Self::VERSION) }}\n}}"
+            )?;
+            writeln!(
+                w,
+                "pub fn rpc_mut(&mut self) -> &mut client::RpcClient<R> {{\n&mut self.rpc\n}}"
+            )?;
+            writeln!(
+                w,
+                "pub fn into_inner(self) -> R {{\nself.rpc.into_inner()\n}}"
+            )?;
+
+            for p in version.procedures.iter() {
+                let number = resolve_number(&p.number, ast)?;
+                write!(w, "pub async fn {}(&mut self", method_name(p))?;
+                for (i, t) in p.args.iter().enumerate() {
+                    write!(w, ", v{}: ", i)?;
+                    print_message_type(&mut w, template, t, ast)?;
+                }
+                write!(w, ") -> Result<")?;
+                match &p.result {
+                    Some(t) => print_message_type(&mut w, template, t, ast)?,
+                    None => write!(w, "()")?,
+                }
+                writeln!(w, ", client::ClientError<R::Error>> {{")?;
+
+                let args = (0..p.args.len())
+                    .map(|i| format!("v{}", i))
+                    .collect::<Vec<_>>();
+                let msg = match args.is_empty() {
+                    true => format!("{}::{}", call, SafeName(&p.name)),
+                    false => format!("{}::{}({})", call, SafeName(&p.name), args.join(", ")),
+                };
+
+                match &p.result {
+                    Some(t) => {
+                        writeln!(w, "let mut v = self.rpc.call({}, &{}).await?;", number, msg)?;
+                        write!(w, "Ok(")?;
+                        print_decode_array(
+                            &mut w,
+                            template,
+                            &ArrayType::None(t.clone()),
+                            ast,
+                            TypeResolve::UseAlias,
+                            template.ref_type(),
+                        )?;
+                        writeln!(w, ")")?;
+                    }
+                    None => {
+                        writeln!(w, "self.rpc.call({}, &{}).await?;", number, msg)?;
+                        writeln!(w, "Ok(())")?;
+                    }
+                }
+                writeln!(w, "}}")?;
+            }
+            writeln!(w, "}}")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bytes::Bytes;
+    use super::*;
+
+    macro_rules! test_convert {
+        ($name: ident, $input: expr, $want: expr) => {
+            #[test]
+            fn $name() {
+                let ast = Ast::new($input).unwrap();
+
+                let mut got = String::new();
+                print_clients(&mut got, Bytes, &ast).unwrap();
+
+                assert_eq!(got, $want);
+            }
+        };
+    }
+
+    test_convert!(
+        test_client,
+        r#"
+            const PROC_GETATTR = 1;
+            struct GETATTR3args {
+                uint32_t a;
+            };
+            struct GETATTR3res {
+                uint32_t b;
+            };
+            program NFS_PROGRAM {
+                version NFS_V3 {
+                    void NFSPROC3_NULL(void) = 0;
+                    GETATTR3res NFSPROC3_GETATTR(GETATTR3args) = PROC_GETATTR;
+                    int NFSPROC3_MULTI(unsigned int, string) = 0x10;
+                } = 3;
+            } = 100003;
+        "#,
+        r#"/// A client for version 3 of the NFS_PROGRAM program.
+#[derive(Debug)]
+pub struct NFS_V3Client<R> {
+rpc: client::RpcClient<R>,
+}
+impl<R: client::Transport> NFS_V3Client<R> {
+pub const PROGRAM: u32 = 100003;
+pub const VERSION: u32 = 3;
+pub fn new(transport: R) -> Self {
+Self { rpc: client::RpcClient::new(transport, Self::PROGRAM, Self::VERSION) }
+}
+pub fn rpc_mut(&mut self) -> &mut client::RpcClient<R> {
+&mut self.rpc
+}
+pub fn into_inner(self) -> R {
+self.rpc.into_inner()
+}
+pub async fn nfsproc3_null(&mut self) -> Result<(), client::ClientError<R::Error>> {
+self.rpc.call(0, &NFS_V3Call::NFSPROC3_NULL).await?;
+Ok(())
+}
+pub async fn nfsproc3_getattr(&mut self, v0: GETATTR3args) -> Result<GETATTR3res, client::ClientError<R::Error>> {
+let mut v = self.rpc.call(1, &NFS_V3Call::NFSPROC3_GETATTR(v0)).await?;
+Ok(GETATTR3res::try_from(&mut v)?)
+}
+pub async fn nfsproc3_multi(&mut self, v0: u32, v1: String) -> Result<i32, client::ClientError<R::Error>> {
+let mut v = self.rpc.call(0x10, &NFS_V3Call::NFSPROC3_MULTI(v0, v1)).await?;
+Ok(v.read_i32()?)
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_client_generic,
+        r#"
+            typedef opaque fhandle<>;
+            program P {
+                version V1 {
+                    fhandle LOOKUP(string) = 1;
+                    void WRITE(fhandle, opaque) = 2;
+                } = 1;
+            } = 0x20000001;
+        "#,
+        r#"/// A client for version 1 of the P program.
+#[derive(Debug)]
+pub struct V1Client<R> {
+rpc: client::RpcClient<R>,
+}
+impl<R: client::Transport> V1Client<R> {
+pub const PROGRAM: u32 = 0x20000001;
+pub const VERSION: u32 = 1;
+pub fn new(transport: R) -> Self {
+Self { rpc: client::RpcClient::new(transport, Self::PROGRAM, Self::VERSION) }
+}
+pub fn rpc_mut(&mut self) -> &mut client::RpcClient<R> {
+&mut self.rpc
+}
+pub fn into_inner(self) -> R {
+self.rpc.into_inner()
+}
+pub async fn lookup(&mut self, v0: String) -> Result<fhandle<Bytes>, client::ClientError<R::Error>> {
+let mut v = self.rpc.call(1, &V1Call::<Bytes>::LOOKUP(v0)).await?;
+Ok(fhandle::try_from(&mut v)?)
+}
+pub async fn write(&mut self, v0: fhandle<Bytes>, v1: Bytes) -> Result<(), client::ClientError<R::Error>> {
+self.rpc.call(2, &V1Call::<Bytes>::WRITE(v0, v1)).await?;
+Ok(())
+}
+}
+"#
+    );
+}
@@ -0,0 +1,637 @@
+use super::{is_mapped, Generics, Message, NonDigitName, SafeName};
+use crate::ast::{indexes::*, ArraySize, ArrayType, Ast, BasicType};
+use crate::Result;
+
+pub fn print_impl_encode<W: std::fmt::Write>(mut w: W, ast: &Ast) -> Result<()> {
+    let generics = Generics::new(ast);
+
+    for item in ast.emitted_types() {
+        match item {
+            AstType::Struct(v) => {
+                let generic = ast.generics().contains(v.name()).then_some(generics);
+                print_impl(&mut w, v.name(), generic, |w| {
+                    for f in v.fields.iter() {
+                        let field = format!("self.{}", SafeName(&f.field_name));
+
+                        // Optional fields are an Option<Box<T>>, which encodes
+                        // the "value follows" bool itself.
+                        if f.is_optional {
+                            match is_mapped(ast, f.field_value.unwrap_array()) {
+                                true => writeln!(w, "{}.encode_mapped(buf)?;", field)?,
+                                false => writeln!(w, "{}.encode(buf)?;", field)?,
+                            }
+                            continue;
+                        }
+
+                        print_encode_array(w, &field, &f.field_value, ast)?;
+                    }
+                    Ok(())
+                })?;
+            }
+
+            AstType::Union(v) => {
+                let generic = ast.generics().contains(v.name()).then_some(generics);
+                print_impl(&mut w, v.name(), generic, |w| {
+                    writeln!(w, "match self {{")?;
+                    for case in v.cases.iter() {
+                        // A single case statement may have many case values tied to it
+                        // if fallthrough values are used:
+                        //
+                        // 	case 1:
+                        // 	case 2:
+                        // 		// statement
+                        //
+                        for c_value in case.case_values.iter() {
+                            writeln!(w, "Self::{}(inner) => {{", NonDigitName(SafeName(c_value)))?;
+                            print_discriminant(w, &v.switch.var_type, c_value, ast)?;
+                            print_encode_array(w, "inner", &case.field_value, ast)?;
+                            writeln!(w, "}}")?;
+                        }
+                    }
+
+                    // There may also be several "void" cases
+                    for c in v.void_cases.iter() {
+                        if c == "default" {
+                            writeln!(w, "Self::default(d) => d.encode(buf)?,")?;
+                            continue;
+                        }
+
+                        writeln!(w, "Self::{} => {{", NonDigitName(SafeName(c)))?;
+                        print_discriminant(w, &v.switch.var_type, c, ast)?;
+                        writeln!(w, "}}")?;
+                    }
+
+                    // The default case carries the discriminant read from the
+                    // wire.
+                    if let Some(ref d) = v.default {
+                        writeln!(w, "Self::default(d, inner) => {{")?;
+                        writeln!(w, "d.encode(buf)?;")?;
+                        print_encode_array(w, "inner", &d.field_value, ast)?;
+                        writeln!(w, "}}")?;
+                    }
+
+                    writeln!(w, "}}")?;
+                    Ok(())
+                })?;
+            }
+
+            AstType::Enum(v) => {
+                print_impl(&mut w, &v.name, None, |w| {
+                    writeln!(w, "buf.put_i32(self.value());")?;
+                    Ok(())
+                })?;
+            }
+
+            AstType::Typedef(v) => {
+                // No typedefs to self - these types are not generated, and
+                // neither are mapped typedefs.
+                let name = v.alias.unwrap_array().as_str();
+                if v.target == *v.alias.unwrap_array() || ast.mappings().get(name).is_some() {
+                    continue;
+                }
+
+                let generic = ast.generics().contains(name).then_some(generics);
+                print_impl(&mut w, name, generic, |w| {
+                    print_encode_array(w, "self.0", &v.target_array(), ast)
+                })?;
+            }
+        }
+    }
+
+    // The RPC message enums encode only the procedure arguments or result -
+    // the procedure number is carried in the RPC message header.
+    for program in ast.emitted_programs() {
+        for version in program.versions.iter() {
+            for msg in Message::all().iter() {
+                let name = msg.type_name(version);
+                let generic = msg.is_generic(version, ast).then_some(generics);
+                print_impl(&mut w, &name, generic, |w| {
+                    writeln!(w, "match self {{")?;
+                    for p in version.procedures.iter() {
+                        let types = msg.types(p);
+                        if types.is_empty() {
+                            writeln!(w, "Self::{} => {{}}", SafeName(&p.name))?;
+                            continue;
+                        }
+
+                        let vars = (0..types.len())
+                            .map(|i| format!("v{}", i))
+                            .collect::<Vec<_>>();
+
+                        writeln!(w, "Self::{}({}) => {{", SafeName(&p.name), vars.join(", "))?;
+                        for (var, t) in vars.iter().zip(types) {
+                            print_encode_array(w, var, &ArrayType::None(t.clone()), ast)?;
+                        }
+                        writeln!(w, "}}")?;
+                    }
+                    writeln!(w, "}}")?;
+                    Ok(())
+                })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the `impl Encode` block around the output of func, for all values
+/// of the `generic` parameter of the type, if any.
+///
+/// `func` should write the body of the `encode` implementation to `w`, using
+/// `buf` as the `BufMut` destination.
+fn print_impl<W: std::fmt::Write, F: Fn(&mut W) -> Result<()>>(
+    mut w: W,
+    name: &str,
+    generic: Option<Generics>,
+    func: F,
+) -> Result<()> {
+    if let Some(g) = generic {
+        writeln!(
+            w,
+            "impl<{p}> Encode for {}<{p}>{} {{",
+            name,
+            g.where_clause(),
+            p = g.param()
+        )?;
+    } else {
+        writeln!(w, "impl Encode for {} {{", name)?;
+    }
+
+    writeln!(
+        w,
+        "fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {{"
+    )?;
+    func(&mut w)?;
+    writeln!(w, "Ok(())\n}}\n}}")?;
+    Ok(())
+}
+
+/// Generates the code to encode the value `expr` of type `t` into a `BufMut`
+/// called `buf`.
+pub(super) fn print_encode_array<W: std::fmt::Write>(
+    w: &mut W,
+    expr: &str,
+    t: &ArrayType<BasicType>,
+    ast: &Ast,
+) -> Result<()> {
+    // Mapped types are encoded through their wire representation.
+    if is_mapped(ast, t.unwrap_array()) {
+        match t {
+            ArrayType::VariableSize(_, size) => writeln!(
+                w,
+                "buf.write_variable_array_with(&{}, {}, |buf, v| v.encode_mapped(buf))?;",
+                expr,
+                print_size(size, ast)?
+            )?,
+            _ => writeln!(w, "{}.encode_mapped(buf)?;", expr)?,
+        }
+        return Ok(());
+    }
+
+    match t {
+        ArrayType::None(BasicType::Opaque) => {
+            writeln!(w, "buf.write_variable_bytes({}.as_ref(), None)?;", expr)?
+        }
+        ArrayType::None(BasicType::String) => writeln!(w, "buf.write_string(&{}, None)?;", expr)?,
+        ArrayType::None(_) => writeln!(w, "{}.encode(buf)?;", expr)?,
+
+        ArrayType::FixedSize(BasicType::Opaque, size) => writeln!(
+            w,
+            "buf.write_bytes({}.as_ref(), {})?;",
+            expr,
+            resolve_size(size, ast)?
+        )?,
+        ArrayType::FixedSize(BasicType::String, _) => {
+            return Err("unexpected fixed length string".into())
+        }
+        // Fixed size arrays are encoded element by element, with no length
+        // prefix.
+        ArrayType::FixedSize(_, _) => writeln!(w, "{}.encode(buf)?;", expr)?,
+
+        ArrayType::VariableSize(t, size) => {
+            let size = print_size(size, ast)?;
+            match t {
+                BasicType::Opaque => {
+                    writeln!(w, "buf.write_variable_bytes({}.as_ref(), {})?;", expr, size)?
+                }
+                BasicType::String => writeln!(w, "buf.write_string(&{}, {})?;", expr, size)?,
+                _ => writeln!(w, "buf.write_variable_array(&{}, {})?;", expr, size)?,
+            }
+        }
+    };
+
+    Ok(())
+}
+
+/// Returns the `Option` maximum length of a variable size array of `size`.
+fn print_size(size: &Option<ArraySize>, ast: &Ast) -> Result<String> {
+    Ok(match size {
+        Some(s) => format!("Some({})", resolve_size(s, ast)?),
+        None => "None".to_string(),
+    })
+}
+
+/// Generates the code to write the union discriminant for `case_value`, as the
+/// wire type of the union switch variable `switch`.
+fn print_discriminant<W: std::fmt::Write>(
+    w: &mut W,
+    switch: &BasicType,
+    case_value: &str,
+    ast: &Ast,
+) -> Result<()> {
+    // Resolve the switch type to the primitive type written to the wire.
+    let (put, cast) = match resolve_wire_type(switch, ast)? {
+        BasicType::U32 | BasicType::Bool => ("put_u32", "u32"),
+        BasicType::I32 => ("put_i32", "i32"),
+        BasicType::U64 => ("put_u64", "u64"),
+        BasicType::I64 => ("put_i64", "i64"),
+        t => return Err(format!("invalid union switch type {}", t).into()),
+    };
+
+    // The case value may be a declared constant, an enum value or a literal.
+    let value = ast
+        .constants()
+        .get(case_value)
+        .map(|c| match c {
+            ConstantType::ConstValue(v) => v.to_string(),
+            ConstantType::EnumValue { enum_name, variant } => {
+                format!("{}::{}", enum_name, variant)
+            }
+        })
+        .unwrap_or_else(|| SafeName(case_value).to_string());
+
+    writeln!(w, "buf.{}({} as {});", put, value, cast)?;
+    Ok(())
+}
+
+/// Follows the typedef chain for `t`, returning the primitive type used to
+/// represent it on the wire.
+///
+/// Enums are always represented as an `I32`.
+fn resolve_wire_type(t: &BasicType, ast: &Ast) -> Result<BasicType> {
+    match t {
+        BasicType::Ident(i) => match ast.types().get(i) {
+            Some(AstType::Typedef(v)) => resolve_wire_type(&v.target, ast),
+            Some(AstType::Enum(_)) => Ok(BasicType::I32),
+            Some(_) => Err(format!("invalid union switch type {}", i).into()),
+            None => Err(ast.unknown_type(i)),
+        },
+        t => Ok(t.clone()),
+    }
+}
+
+/// Returns the numeric value of `size`, resolving constants.
+fn resolve_size(size: &ArraySize, ast: &Ast) -> Result<u32> {
+    match size {
+        ArraySize::Known(s) => Ok(*s),
+        ArraySize::Constant(c) => ast.constants().size(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_convert {
+        ($name: ident, $input: expr, $want: expr) => {
+            #[test]
+            fn $name() {
+                let ast = Ast::new($input).unwrap();
+
+                let mut got = String::new();
+                print_impl_encode(&mut got, &ast).unwrap();
+
+                assert_eq!(got, $want);
+            }
+        };
+    }
+
+    test_convert!(
+        test_struct_basic_types,
+        r#"
+			struct small {
+				unsigned int a;
+				unsigned hyper b;
+				int c;
+				hyper d;
+				float e;
+				double f;
+				string g;
+				bool h;
+				opaque i;
+			};
+		"#,
+        r#"impl<T> Encode for small<T> where T: AsRef<[u8]> + Debug {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+self.a.encode(buf)?;
+self.b.encode(buf)?;
+self.c.encode(buf)?;
+self.d.encode(buf)?;
+self.e.encode(buf)?;
+self.f.encode(buf)?;
+buf.write_string(&self.g, None)?;
+self.h.encode(buf)?;
+buf.write_variable_bytes(self.i.as_ref(), None)?;
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_struct_opaque_fields,
+        r#"
+            const SIZE = 3;
+			struct small {
+				opaque a<>;
+				opaque b<42>;
+				opaque c<SIZE>;
+				opaque d[2];
+				opaque e[SIZE];
+			};
+		"#,
+        r#"impl<T> Encode for small<T> where T: AsRef<[u8]> + Debug {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+buf.write_variable_bytes(self.a.as_ref(), None)?;
+buf.write_variable_bytes(self.b.as_ref(), Some(42))?;
+buf.write_variable_bytes(self.c.as_ref(), Some(3))?;
+buf.write_bytes(self.d.as_ref(), 2)?;
+buf.write_bytes(self.e.as_ref(), 3)?;
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_struct_string_fields,
+        r#"
+			struct small {
+				string a<>;
+				string b<42>;
+			};
+		"#,
+        r#"impl Encode for small {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+buf.write_string(&self.a, None)?;
+buf.write_string(&self.b, Some(42))?;
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_struct_arrays,
+        r#"
+			struct small {
+				unsigned int a[2];
+				other b<>;
+				other c<8>;
+			};
+		"#,
+        r#"impl Encode for small {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+self.a.encode(buf)?;
+buf.write_variable_array(&self.b, None)?;
+buf.write_variable_array(&self.c, Some(8))?;
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_struct_option_reserved_keyword,
+        r#"
+			struct small {
+				small *type;
+			};
+		"#,
+        r#"impl Encode for small {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+self.type_v.encode(buf)?;
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_enum,
+        r#"
+			enum Status {
+				MODE4_SUID = 1,
+				MODE4_OTHER = 2
+			};
+		"#,
+        r#"impl Encode for Status {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+buf.put_i32(self.value());
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_union,
+        r#"
+            const CONST_TWO = 2;
+			union u_type_name switch (unsigned int s) {
+				case 1:
+					u32 a;
+				case CONST_TWO:
+					opaque b;
+				case 3:
+				case 4:
+					void;
+			};
+		"#,
+        r#"impl<T> Encode for u_type_name<T> where T: AsRef<[u8]> + Debug {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+match self {
+Self::v_1(inner) => {
+buf.put_u32(1 as u32);
+inner.encode(buf)?;
+}
+Self::CONST_TWO(inner) => {
+buf.put_u32(2 as u32);
+buf.write_variable_bytes(inner.as_ref(), None)?;
+}
+Self::v_3 => {
+buf.put_u32(3 as u32);
+}
+Self::v_4 => {
+buf.put_u32(4 as u32);
+}
+}
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_union_enum_switch_default,
+        r#"
+            enum nfsstat4 {
+                NFS4_OK = 0,
+                NFS4ERR_PERM = 1
+            };
+            typedef nfsstat4 status_alias;
+			union LOCKU4res switch (status_alias status) {
+				case NFS4_OK:
+					stateid4 lock_stateid;
+				default:
+					void;
+			};
+		"#,
+        r#"impl Encode for LOCKU4res {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+match self {
+Self::NFS4_OK(inner) => {
+buf.put_i32(nfsstat4::NFS4_OK as i32);
+inner.encode(buf)?;
+}
+Self::default(d) => d.encode(buf)?,
+}
+Ok(())
+}
+}
+impl Encode for nfsstat4 {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+buf.put_i32(self.value());
+Ok(())
+}
+}
+impl Encode for status_alias {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+self.0.encode(buf)?;
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_union_bool_switch_default_field,
+        r#"
+			union locker4 switch (bool new_lock_owner) {
+				case TRUE:
+					open_to_lock_owner4 open_owner;
+				default:
+					hyper value;
+			};
+		"#,
+        r#"impl Encode for locker4 {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+match self {
+Self::TRUE(inner) => {
+buf.put_u32(true as u32);
+inner.encode(buf)?;
+}
+Self::default(d, inner) => {
+d.encode(buf)?;
+inner.encode(buf)?;
+}
+}
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_program_messages,
+        r#"
+            program P {
+                version V1 {
+                    void NULL(void) = 0;
+                    int ADD(int, int) = 1;
+                    void NAME(string) = 2;
+                } = 1;
+            } = 1;
+		"#,
+        r#"impl Encode for V1Call {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+match self {
+Self::NULL => {}
+Self::ADD(v0, v1) => {
+v0.encode(buf)?;
+v1.encode(buf)?;
+}
+Self::NAME(v0) => {
+buf.write_string(&v0, None)?;
+}
+}
+Ok(())
+}
+}
+impl Encode for V1Reply {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+match self {
+Self::NULL => {}
+Self::ADD(v0) => {
+v0.encode(buf)?;
+}
+Self::NAME => {}
+}
+Ok(())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_typedef,
+        r#"
+            const SIZE = 8;
+            typedef uint32_t acetype4;
+            typedef opaque utf8string<>;
+            typedef opaque verifier4[SIZE];
+            typedef small alias<4>;
+            typedef small fixed_alias[2];
+			struct small {
+				uint32_t id;
+			};
+		"#,
+        r#"impl Encode for acetype4 {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+self.0.encode(buf)?;
+Ok(())
+}
+}
+impl Encode for alias {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+buf.write_variable_array(&self.0, Some(4))?;
+Ok(())
+}
+}
+impl Encode for fixed_alias {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+self.0.encode(buf)?;
+Ok(())
+}
+}
+impl Encode for small {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+self.id.encode(buf)?;
+Ok(())
+}
+}
+impl<T> Encode for utf8string<T> where T: AsRef<[u8]> + Debug {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+buf.write_variable_bytes(self.0.as_ref(), None)?;
+Ok(())
+}
+}
+impl<T> Encode for verifier4<T> where T: AsRef<[u8]> + Debug {
+fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+buf.write_bytes(self.0.as_ref(), 8)?;
+Ok(())
+}
+}
+"#
+    );
+}
@@ -1,4 +1,4 @@
-use super::{NonDigitName, SafeName};
+use super::{BoundedType, NonDigitName, SafeName};
 use crate::ast::{indexes::*, ArraySize, ArrayType, Ast, BasicType};
 use crate::impls::template::*;
 use crate::Result;
@@ -9,7 +9,7 @@ use crate::Result;
 /// `UseTarget` instructs the callee to use the target type the alias
 /// references.
 #[derive(Debug, Copy, Clone)]
-enum TypeResolve {
+pub(super) enum TypeResolve {
     UseAlias,
     UseTarget,
 }
@@ -23,12 +23,18 @@ impl TypeResolve {
     }
 }
 
+/// Prints the `TryFrom` implementations decoding each type in `ast` from the
+/// buffer type of `template`.
+///
+/// If `bounded` is true, bounded strings and bounded or fixed size opaques are
+/// decoded into the length-checked runtime newtypes.
 pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
     mut w: W,
     template: T,
     ast: &Ast,
+    bounded: bool,
 ) -> Result<()> {
-    for item in ast.types().iter() {
+    for item in ast.emitted_types() {
         match item {
             AstType::Struct(v) => {
                 print_try_from(&mut w, template, v.name.as_str(), ast, |w, try_from| {
@@ -46,15 +52,23 @@ pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
                             // 		}
                             writeln!(w, "{{ match v.read_u32()? {{")?;
                             writeln!(w, "0 => None,")?;
-                            writeln!(
+                            write!(w, "1 => Some(Box::new(")?;
+                            print_decode_basic_type(
                                 w,
-                                "1 => Some(Box::new({}::try_from({})?)),",
+                                template,
                                 f.field_value.unwrap_array(),
-                                try_from
+                                ast,
+                                TypeResolve::UseAlias,
+                                try_from,
                             )?;
+                            writeln!(w, "?)),")?;
                             writeln!(w, "d => return Err(Error::UnknownOptionVariant(d)),")?;
                             writeln!(w, "}}}},")?;
                         } else {
+                            let checked = BoundedType::new(&f.field_value).filter(|_| bounded);
+                            if let Some(ref b) = checked {
+                                write!(w, "{}::new(", b.name)?;
+                            }
                             print_decode_array(
                                 w,
                                 template,
@@ -65,6 +79,9 @@ pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
                                 TypeResolve::UseAlias,
                                 try_from,
                             )?;
+                            if checked.is_some() {
+                                write!(w, ")?")?;
+                            }
                             writeln!(w, ",")?;
                         }
                     }
@@ -78,6 +95,7 @@ pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
                     write!(w, "let {} = ", SafeName(&v.switch.var_name))?;
                     print_decode_basic_type(
                         w,
+                        template,
                         &v.switch.var_type,
                         ast,
                         TypeResolve::UseTarget,
@@ -95,28 +113,7 @@ pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
                         // 		// statement
                         //
                         for c_value in c.case_values.iter() {
-                            // The case value may be a declared constant or enum
-                            // value.
-                            //
-                            // Enum values are i32, which may not be the same as
-                            // the type of the variable this code is matching
-                            // on, so write enums in a longer form to allow a
-                            // primitive cast.
-                            let matcher = ast
-                                .constants()
-                                .get(c_value.as_str())
-                                .map(|c| match *c {
-                                    ConstantType::ConstValue(ref v) => SafeName(v).to_string(),
-                                    ConstantType::EnumValue {
-                                        ref enum_name,
-                                        ref variant,
-                                    } => format!(
-                                        "c if c == {}::{} as {}",
-                                        enum_name, variant, v.switch.var_type,
-                                    ),
-                                })
-                                .unwrap_or_else(|| SafeName(c_value).to_string());
-
+                            let matcher = case_matcher(c_value, &v.switch.var_type, ast);
                             write!(
                                 w,
                                 "{} => Self::{}(",
@@ -142,22 +139,10 @@ pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
                         let matcher = match variant {
                             "default" => {
                                 did_void_default = true;
-                                "_".to_string()
+                                writeln!(w, "d => Self::default(d),")?;
+                                continue;
                             }
-                            other => ast
-                                .constants()
-                                .get(other)
-                                .map(|c| match *c {
-                                    ConstantType::ConstValue(ref v) => SafeName(v).to_string(),
-                                    ConstantType::EnumValue {
-                                        ref enum_name,
-                                        ref variant,
-                                    } => format!(
-                                        "c if c == {}::{} as {}",
-                                        enum_name, variant, v.switch.var_type,
-                                    ),
-                                })
-                                .unwrap_or_else(|| other.to_string()),
+                            other => case_matcher(other, &v.switch.var_type, ast),
                         };
                         writeln!(
                             w,
@@ -170,7 +155,7 @@ pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
                     // Write a default case if present, else a catch all case that
                     // returns an error.
                     if let Some(ref d) = v.default {
-                        write!(w, "_ => Self::default(")?;
+                        write!(w, "d => Self::default(d, ")?;
                         print_decode_array(
                             w,
                             template,
@@ -192,22 +177,33 @@ pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
 
             AstType::Enum(v) => {
                 print_try_from(&mut w, template, v.name.as_str(), ast, |w, _try_from| {
-                    writeln!(w, "Ok(match v.read_i32()? {{")?;
-                    for variant in v.variants.iter() {
-                        writeln!(w, "{} => Self::{},", variant.value, variant.name)?;
-                    }
-                    writeln!(w, "d => return Err(Error::UnknownVariant(d as i32)),\n}})")?;
+                    // Checked by the generated TryFrom<i32> implementation.
+                    writeln!(w, "Self::try_from(v.read_i32()?)")?;
                     Ok(())
                 })?
             }
 
+            // Mapped typedefs are replaced by the external type, decoded by
+            // print_decode_mapped() at each use.
+            AstType::Typedef(v)
+                if ast
+                    .mappings()
+                    .get(v.alias.unwrap_array().as_str())
+                    .is_some() => {}
+
             AstType::Typedef(v) => print_try_from(
                 &mut w,
                 template,
                 v.alias.unwrap_array().as_str(),
                 ast,
                 |w, try_from| {
+                    let target = v.target_array();
+                    let checked = BoundedType::new(&target).filter(|_| bounded);
+
                     write!(w, "Ok(Self(")?;
+                    if let Some(ref b) = checked {
+                        write!(w, "{}::new(", b.name)?;
+                    }
                     print_decode_array(
                         w,
                         template,
@@ -216,6 +212,9 @@ pub fn print_impl_from<W: std::fmt::Write, T: FromTemplate>(
                         TypeResolve::UseTarget,
                         try_from,
                     )?;
+                    if checked.is_some() {
+                        write!(w, ")?")?;
+                    }
                     writeln!(w, "))")?;
                     Ok(())
                 },
@@ -244,13 +243,20 @@ fn print_try_from<
     if ast.generics().contains(name) {
         write!(
             w,
-            "impl TryFrom<{}> for {}<{}>",
+            "impl{} TryFrom<{}> for {}<{}>",
+            template.impl_generics(),
             template.try_from(),
             name,
             template.type_name(),
         )?;
     } else {
-        write!(w, r#"impl TryFrom<{}> for {}"#, template.try_from(), name)?;
+        write!(
+            w,
+            r#"impl{} TryFrom<{}> for {}"#,
+            template.impl_generics(),
+            template.try_from(),
+            name
+        )?;
     }
     writeln!(
         w,
@@ -268,7 +274,7 @@ fn try_from(mut v: {}) -> Result<Self, Self::Error> {{"#,
     Ok(())
 }
 
-fn print_decode_array<W, T: FromTemplate>(
+pub(super) fn print_decode_array<W, T: FromTemplate>(
     w: &mut W,
     template: T,
     t: &ArrayType<BasicType>,
@@ -292,11 +298,11 @@ where
 
         match field {
             BasicType::Opaque => write!(w, "v.read_bytes({})?", size)?,
-            BasicType::String => unreachable!("unexpected fixed length string"),
+            BasicType::String => return Err("unexpected fixed length string".into()),
             _ => {
                 writeln!(w, "[")?;
                 for _i in 0..size {
-                    print_decode_basic_type(w, t, ast, resolve_typedefs, try_from)?;
+                    print_decode_basic_type(w, template, t, ast, resolve_typedefs, try_from)?;
                     writeln!(w, "?,")?;
                 }
                 write!(w, "]")?;
@@ -308,6 +314,7 @@ where
     // Print a length-prefixed variable sized array, or a string with a maximum value.
     let print_variable = |w: &mut W, t: &BasicType, size: Option<u32>| -> Result<()> {
         let mut type_str = t.to_string();
+        let mut field = t.clone();
 
         // If requested, resolve a typedef the type to the target type.
         if !resolve_typedefs.use_alias() {
@@ -315,7 +322,11 @@ where
                 .types()
                 .get(&type_str)
                 .map(|t| t.to_string())
-                .unwrap_or(type_str)
+                .unwrap_or(type_str);
+
+            if let Some(typedef) = ast.types().typedef_target(t.as_str()) {
+                field = typedef.target.clone();
+            }
         }
 
         if ast.generics().contains(type_str.as_str()) {
@@ -326,9 +337,42 @@ where
             .map(|s| format!("Some({})", s))
             .unwrap_or_else(|| "None".to_string());
 
-        match t {
+        match field {
             BasicType::Opaque => write!(w, "v.read_variable_bytes({})?", size)?,
             BasicType::String => write!(w, "v.read_string({})?", size)?,
+
+            // The orphan rule prevents implementing TryFrom for primitive
+            // types, so read them with an explicit reader instead.
+            BasicType::U32
+            | BasicType::U64
+            | BasicType::I32
+            | BasicType::I64
+            | BasicType::F32
+            | BasicType::F64
+            | BasicType::Quadruple
+            | BasicType::Bool => {
+                write!(w, "v.read_variable_array_with({}, |v| ", size)?;
+                print_decode_basic_type(w, template, &field, ast, resolve_typedefs, try_from)?;
+                write!(w, ")?")?;
+            }
+            // Mapped types cannot implement TryFrom for the buffer, so are
+            // decoded from their wire representation element by element.
+            //
+            // Types borrowing from a slice are also decoded element by element,
+            // in place, rather than from a copy of the slice advanced by the
+            // wire size of each element.
+            BasicType::Ident(ref i) if ast.mappings().source(i).is_some() || ast.is_borrowed() => {
+                write!(w, "v.read_variable_array_with({}, |v| ", size)?;
+                print_decode_basic_type(
+                    w,
+                    template,
+                    &field,
+                    ast,
+                    resolve_typedefs,
+                    ReferenceType::ByValue,
+                )?;
+                write!(w, ")?")?;
+            }
             _ => write!(w, "v.read_variable_array::<{}>({})?", type_str, size)?,
         };
 
@@ -337,32 +381,18 @@ where
 
     match t {
         ArrayType::None(t) => {
-            print_decode_basic_type(w, t, ast, resolve_typedefs, try_from)?;
+            print_decode_basic_type(w, template, t, ast, resolve_typedefs, try_from)?;
             write!(w, "?")?;
         }
         ArrayType::FixedSize(t, ArraySize::Known(size)) => print_fixed(w, t, *size)?,
         ArrayType::FixedSize(t, ArraySize::Constant(size)) => {
-            // Try and resolve the constant value
-            let size = ast
-                .constants()
-                .get(size.as_str())
-                .map(|v| v.to_string())
-                .ok_or(format!("unknown constant {}", size))?;
-
-            print_fixed(w, t, size.parse()?)?
+            print_fixed(w, t, ast.constants().size(size)?)?
         }
         ArrayType::VariableSize(t, Some(ArraySize::Known(size))) => {
             print_variable(w, t, Some(*size))?
         }
         ArrayType::VariableSize(t, Some(ArraySize::Constant(size))) => {
-            // Try and resolve the constant value
-            let size = ast
-                .constants()
-                .get(size.as_str())
-                .map(|v| v.to_string())
-                .ok_or("unknown constant")?;
-
-            print_variable(w, t, Some(size.parse()?))?;
+            print_variable(w, t, Some(ast.constants().size(size)?))?;
         }
         ArrayType::VariableSize(t, None) => {
             print_variable(w, t, None)?;
@@ -377,8 +407,39 @@ where
 ///
 /// If `t` is a typedef alias, the typedef chain is resolved to the underlying
 /// type.
-fn print_decode_basic_type<W>(
+/// Returns the match pattern for the union case value `case_value`.
+///
+/// The case value may be a declared constant, an enum value or a literal.
+/// Enum values are matched as variants when the switch is an enum, and
+/// otherwise as their `i32` value cast to the type of the switch.
+fn case_matcher(case_value: &str, switch: &BasicType, ast: &Ast) -> String {
+    match ast.constants().get(case_value) {
+        Some(ConstantType::ConstValue(v)) => v.to_string(),
+        Some(ConstantType::EnumValue { enum_name, variant }) if is_enum(switch, ast) => {
+            format!("{}::{}", enum_name, variant)
+        }
+        Some(ConstantType::EnumValue { enum_name, variant }) => {
+            format!("c if c == {}::{} as {}", enum_name, variant, switch)
+        }
+        None => SafeName(case_value).to_string(),
+    }
+}
+
+/// Returns true if `t` is an enum, or a typedef of one.
+fn is_enum(t: &BasicType, ast: &Ast) -> bool {
+    match t {
+        BasicType::Ident(i) => match ast.types().get(i) {
+            Some(AstType::Typedef(v)) => is_enum(&v.target, ast),
+            Some(AstType::Enum(_)) => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn print_decode_basic_type<W, T: FromTemplate>(
     w: &mut W,
+    template: T,
     t: &BasicType,
     ast: &Ast,
     resolve_typedefs: TypeResolve,
@@ -394,10 +455,16 @@ where
         BasicType::I64 => write!(w, "v.read_i64()")?,
         BasicType::F32 => write!(w, "v.read_f32()")?,
         BasicType::F64 => write!(w, "v.read_f64()")?,
+        BasicType::Quadruple => write!(w, "v.read_quadruple()")?,
         BasicType::Bool => write!(w, "v.read_bool()")?,
         BasicType::String => write!(w, "v.read_string(None)")?,
         BasicType::Opaque => write!(w, "v.read_variable_bytes(None)")?,
 
+        // Mapped types are converted from the decoded wire representation.
+        BasicType::Ident(c) if ast.mappings().source(c).is_some() => {
+            print_decode_mapped(w, template, c, ast, try_from)?
+        }
+
         // If typedefs should not be resolved to their targets (for struct
         // fields) just print a try_from() impl for the ident name.
         BasicType::Ident(c) if resolve_typedefs.use_alias() => {
@@ -418,28 +485,73 @@ where
             // Otherwise print the target's try_from, but only go one level down
             // the typedef chain.
             Some(AstType::Typedef(t)) => {
-                return print_decode_basic_type(w, &t.target, ast, TypeResolve::UseAlias, try_from)
+                return print_decode_basic_type(
+                    w,
+                    template,
+                    &t.target,
+                    ast,
+                    TypeResolve::UseAlias,
+                    try_from,
+                )
             }
 
-            None => return Err(format!("unresolvable type {}", c).into()),
+            None => return Err(ast.unknown_type(c)),
         },
     };
     Ok(())
 }
 
+/// Generates the code to decode the external Rust type `path` replacing an
+/// XDR type, by decoding the wire representation and converting it with the
+/// `XdrMapping` implementation of `path`.
+fn print_decode_mapped<W, T: FromTemplate>(
+    w: &mut W,
+    template: T,
+    path: &str,
+    ast: &Ast,
+    try_from: ReferenceType,
+) -> Result<()>
+where
+    W: std::fmt::Write,
+{
+    let name = ast.mappings().source(path).unwrap_or(path);
+
+    write!(w, "<{} as XdrMapping>::from_xdr(", path)?;
+    match ast.types().get(name) {
+        // A mapped typedef has no generated type, so decode its target.
+        Some(AstType::Typedef(v)) => print_decode_array(
+            w,
+            template,
+            &v.target_array(),
+            ast,
+            TypeResolve::UseAlias,
+            try_from,
+        )?,
+        Some(_) => write!(w, "{}::try_from({})?", name, try_from)?,
+        None => return Err(ast.unknown_type(name)),
+    }
+    write!(w, ")")?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::bytes::RefMutBytes;
+    use super::slice::RefMutSlice;
     use super::*;
 
     macro_rules! test_convert {
         ($name: ident, $input: expr, $want: expr) => {
+            test_convert!($name, $input, $want, false);
+        };
+        ($name: ident, $input: expr, $want: expr, $bounded: expr) => {
             #[test]
             fn $name() {
                 let ast = Ast::new($input).unwrap();
 
                 let mut got = String::new();
-                print_impl_from(&mut got, RefMutBytes, &ast).unwrap();
+                print_impl_from(&mut got, RefMutBytes, &ast, $bounded).unwrap();
 
                 assert_eq!(got, $want);
             }
@@ -483,6 +595,81 @@ j: v.read_variable_bytes(None)?,
 "#
     );
 
+    test_convert!(
+        test_struct_c_types,
+        r#"
+			struct small {
+				quadruple a;
+				quadruple b<>;
+				char c;
+				unsigned char d;
+				short e;
+				unsigned	short f;
+				long g;
+				unsigned long h;
+			};
+		"#,
+        r#"impl TryFrom<&mut Bytes> for small {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+Ok(small {
+a: v.read_quadruple()?,
+b: v.read_variable_array_with(None, |v| v.read_quadruple())?,
+c: v.read_i32()?,
+d: v.read_u32()?,
+e: v.read_i32()?,
+f: v.read_u32()?,
+g: v.read_i32()?,
+h: v.read_u32()?,
+})
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_bounded,
+        r#"
+			typedef string name<8>;
+			typedef opaque fixed[4];
+			struct small {
+				string a<4>;
+				opaque b[3];
+				opaque c<5>;
+				string d<>;
+			};
+		"#,
+        r#"impl TryFrom<&mut Bytes> for fixed<Bytes> {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+Ok(Self(FixedOpaque::new(v.read_bytes(4)?)?))
+}
+}
+impl TryFrom<&mut Bytes> for name {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+Ok(Self(BoundedString::new(v.read_string(Some(8))?)?))
+}
+}
+impl TryFrom<&mut Bytes> for small<Bytes> {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+Ok(small {
+a: BoundedString::new(v.read_string(Some(4))?)?,
+b: FixedOpaque::new(v.read_bytes(3)?)?,
+c: BoundedOpaque::new(v.read_variable_bytes(Some(5))?)?,
+d: v.read_string(None)?,
+})
+}
+}
+"#,
+        true
+    );
+
     test_convert!(
         test_struct_opaque_fields,
         r#"
@@ -942,6 +1129,34 @@ d => return Err(Error::UnknownVariant(d as i32)),
 "#
     );
 
+    test_convert!(
+        test_union_literal_case_values,
+        r#"
+			union result switch (int status) {
+			case -1:
+				u32       err;
+			case 010:
+				u64       name;
+			case 0x10:
+				void;
+			};
+		"#,
+        r#"impl TryFrom<&mut Bytes> for result {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+let status = v.read_i32()?;
+Ok(match status {
+-1 => Self::v_neg_1(v.read_u32()?),
+0o10 => Self::v_0o10(v.read_u64()?),
+0x10 => Self::v_0x10,
+d => return Err(Error::UnknownVariant(d as i32)),
+})
+}
+}
+"#
+    );
+
     test_convert!(
         test_union_enum_case_value,
         r#"
@@ -972,11 +1187,7 @@ impl TryFrom<&mut Bytes> for Status {
 type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
-Ok(match v.read_i32()? {
-1 => Self::MODE4_SUID,
-2 => Self::MODE4_OTHER,
-d => return Err(Error::UnknownVariant(d as i32)),
-})
+Self::try_from(v.read_i32()?)
 }
 }
 "#
@@ -1051,7 +1262,7 @@ fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
 let status = v.read_u32()?;
 Ok(match status {
 1 => Self::v_1(v.read_u32()?),
-_ => Self::default(v.read_u64()?),
+d => Self::default(d, v.read_u64()?),
 })
 }
 }
@@ -1076,7 +1287,7 @@ fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
 let status = v.read_u32()?;
 Ok(match status {
 1 => Self::v_1(v.read_u32()?),
-_ => Self::default(v.read_u64()?),
+d => Self::default(d, v.read_u64()?),
 })
 }
 }
@@ -1102,7 +1313,7 @@ fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
 let status = v.read_u32()?;
 Ok(match status {
 1 => Self::v_1(v.read_u32()?),
-_ => Self::default,
+d => Self::default(d),
 })
 }
 }
@@ -1130,7 +1341,7 @@ let status = v.read_u32()?;
 Ok(match status {
 1 => Self::v_1(v.read_u32()?),
 2 => Self::v_2,
-_ => Self::default,
+d => Self::default(d),
 })
 }
 }
@@ -1712,8 +1923,8 @@ type Error = Error;
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
 let set_it = time_how4::try_from(&mut *v)?;
 Ok(match set_it {
-c if c == time_how4::SET_TO_CLIENT_TIME4 as time_how4 => Self::SET_TO_CLIENT_TIME4(v.read_u32()?),
-_ => Self::default,
+time_how4::SET_TO_CLIENT_TIME4 => Self::SET_TO_CLIENT_TIME4(v.read_u32()?),
+d => Self::default(d),
 })
 }
 }
@@ -1721,13 +1932,54 @@ impl TryFrom<&mut Bytes> for time_how4 {
 type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
-Ok(match v.read_i32()? {
-0 => Self::SET_TO_SERVER_TIME4,
-1 => Self::SET_TO_CLIENT_TIME4,
+Self::try_from(v.read_i32()?)
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_union_switch_typedef_enum,
+        r#"
+			enum time_how4 {
+				SET_TO_SERVER_TIME4 = 0,
+				SET_TO_CLIENT_TIME4 = 1
+			};
+			typedef time_how4 how;
+
+			union settime4 switch (how set_it) {
+			case SET_TO_CLIENT_TIME4:
+				u32       time;
+			case SET_TO_SERVER_TIME4:
+				void;
+			};
+		"#,
+        r#"impl TryFrom<&mut Bytes> for how {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+Ok(Self(time_how4::try_from(&mut *v)?))
+}
+}
+impl TryFrom<&mut Bytes> for settime4 {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+let set_it = time_how4::try_from(&mut *v)?;
+Ok(match set_it {
+time_how4::SET_TO_CLIENT_TIME4 => Self::SET_TO_CLIENT_TIME4(v.read_u32()?),
+time_how4::SET_TO_SERVER_TIME4 => Self::SET_TO_SERVER_TIME4,
 d => return Err(Error::UnknownVariant(d as i32)),
 })
 }
 }
+impl TryFrom<&mut Bytes> for time_how4 {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+Self::try_from(v.read_i32()?)
+}
+}
 "#
     );
 
@@ -1858,7 +2110,7 @@ type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
 Ok(small {
-a: v.read_variable_array::<u32>(None)?,
+a: v.read_variable_array_with(None, |v| v.read_u32())?,
 })
 }
 }
@@ -1908,7 +2160,7 @@ type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
 Ok(small {
-a: v.read_variable_array::<u32>(Some(42))?,
+a: v.read_variable_array_with(Some(42), |v| v.read_u32())?,
 })
 }
 }
@@ -1959,7 +2211,7 @@ type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
 Ok(small {
-a: v.read_variable_array::<u32>(Some(42))?,
+a: v.read_variable_array_with(Some(42), |v| v.read_u32())?,
 })
 }
 }
@@ -2155,11 +2407,7 @@ impl TryFrom<&mut Bytes> for a_status {
 type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
-Ok(match v.read_i32()? {
-0 => Self::ZERO,
-1 => Self::ONE,
-d => return Err(Error::UnknownVariant(d as i32)),
-})
+Self::try_from(v.read_i32()?)
 }
 }
 "#
@@ -2191,11 +2439,7 @@ impl TryFrom<&mut Bytes> for a_status {
 type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
-Ok(match v.read_i32()? {
-0 => Self::ZERO,
-1 => Self::ONE,
-d => return Err(Error::UnknownVariant(d as i32)),
-})
+Self::try_from(v.read_i32()?)
 }
 }
 impl TryFrom<&mut Bytes> for alias {
@@ -2609,7 +2853,7 @@ Ok(Self(v.read_variable_bytes(None)?))
 type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
-Ok(Self(v.read_variable_array::<u32>(None)?))
+Ok(Self(v.read_variable_array_with(None, |v| v.read_u32())?))
 }
 }
 "#
@@ -2647,12 +2891,7 @@ Ok(Self(v.read_bytes(8)?))
 type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
-Ok(match v.read_i32()? {
-CONST_ONE => Self::ONE,
-CONST_TWO => Self::TWO,
-CONST_THREE => Self::THREE,
-d => return Err(Error::UnknownVariant(d as i32)),
-})
+Self::try_from(v.read_i32()?)
 }
 }
 "#
@@ -2701,12 +2940,7 @@ d => return Err(Error::UnknownVariant(d as i32)),
 type Error = Error;
 
 fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
-Ok(match v.read_i32()? {
-1 => Self::ONE,
-2 => Self::TWO,
-3 => Self::THREE,
-d => return Err(Error::UnknownVariant(d as i32)),
-})
+Self::try_from(v.read_i32()?)
 }
 }
 impl TryFrom<&mut Bytes> for u_type_name {
@@ -2724,4 +2958,127 @@ d => return Err(Error::UnknownVariant(d as i32)),
 }
 "#
     );
+
+    #[test]
+    fn test_mapped_types() {
+        let mappings = vec![
+            ("uuid4".to_string(), "crate::Uuid".to_string()),
+            ("nfstime4".to_string(), "std::time::SystemTime".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let ast = Ast::with_mappings(
+            r#"
+			typedef opaque uuid4[16];
+			struct nfstime4 {
+				hyper seconds;
+			};
+			struct attrs {
+				uuid4 id;
+				nfstime4 *atime;
+				nfstime4 history<>;
+			};
+		"#,
+            &mappings,
+        )
+        .unwrap();
+
+        let mut got = String::new();
+        print_impl_from(&mut got, RefMutBytes, &ast, false).unwrap();
+
+        assert_eq!(
+            got,
+            r#"impl TryFrom<&mut Bytes> for attrs {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+Ok(attrs {
+id: <crate::Uuid as XdrMapping>::from_xdr(v.read_bytes(16)?)?,
+atime: { match v.read_u32()? {
+0 => None,
+1 => Some(Box::new(<std::time::SystemTime as XdrMapping>::from_xdr(nfstime4::try_from(&mut *v)?)?)),
+d => return Err(Error::UnknownOptionVariant(d)),
+}},
+history: v.read_variable_array_with(None, |v| <std::time::SystemTime as XdrMapping>::from_xdr(nfstime4::try_from(&mut *v)?))?,
+})
+}
+}
+impl TryFrom<&mut Bytes> for nfstime4 {
+type Error = Error;
+
+fn try_from(mut v: &mut Bytes) -> Result<Self, Self::Error> {
+Ok(nfstime4 {
+seconds: v.read_i64()?,
+})
+}
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let ast = Ast::parse(
+            r#"
+			struct point {
+				int x;
+			};
+			struct entry {
+				string name<>;
+				opaque data<>;
+				point pts<>;
+				entry *next;
+			};
+			struct listing {
+				entry entries<8>;
+			};
+		"#,
+            &Default::default(),
+            true,
+        )
+        .unwrap();
+
+        let mut got = String::new();
+        print_impl_from(&mut got, RefMutSlice, &ast, false).unwrap();
+
+        assert_eq!(
+            got,
+            r#"impl<'a> TryFrom<&mut &'a [u8]> for entry<'a> {
+type Error = Error;
+
+fn try_from(mut v: &mut &'a [u8]) -> Result<Self, Self::Error> {
+Ok(entry {
+name: v.read_string(None)?,
+data: v.read_variable_bytes(None)?,
+pts: v.read_variable_array_with(None, |v| point::try_from(&mut *v))?,
+next: { match v.read_u32()? {
+0 => None,
+1 => Some(Box::new(entry::try_from(&mut *v)?)),
+d => return Err(Error::UnknownOptionVariant(d)),
+}},
+})
+}
+}
+impl<'a> TryFrom<&mut &'a [u8]> for listing<'a> {
+type Error = Error;
+
+fn try_from(mut v: &mut &'a [u8]) -> Result<Self, Self::Error> {
+Ok(listing {
+entries: v.read_variable_array_with(Some(8), |v| entry::try_from(&mut *v))?,
+})
+}
+}
+impl<'a> TryFrom<&mut &'a [u8]> for point {
+type Error = Error;
+
+fn try_from(mut v: &mut &'a [u8]) -> Result<Self, Self::Error> {
+Ok(point {
+x: v.read_i32()?,
+})
+}
+}
+"#
+        );
+    }
 }
@@ -0,0 +1,135 @@
+use super::encode::print_encode_array;
+use crate::ast::{indexes::*, ArrayType, Ast, BasicType};
+use crate::Result;
+
+/// Prints the `EncodeMapped` implementations for the external Rust types
+/// replacing XDR types in `ast`, converting each to the wire representation of
+/// the XDR type with its `XdrMapping` implementation.
+///
+/// Decoding is generated inline wherever a mapped type is used.
+pub fn print_impl_mappings<W: std::fmt::Write>(mut w: W, ast: &Ast) -> Result<()> {
+    for (name, path) in ast.mappings().iter().filter(|(name, _)| ast.emits(name)) {
+        // The wire representation of a mapped typedef is its target, with the
+        // array of the alias, while other types keep their generated type.
+        let target = match ast.types().get(name) {
+            Some(AstType::Typedef(v)) => v.target_array(),
+            Some(_) => ArrayType::None(BasicType::Ident(name.to_string())),
+            None => return Err(ast.unknown_type(name)),
+        };
+
+        writeln!(w, "impl EncodeMapped for {} {{", path)?;
+        writeln!(
+            w,
+            "fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {{"
+        )?;
+        writeln!(w, "let wire = XdrMapping::to_xdr(self);")?;
+        print_encode_array(&mut w, "wire", &target, ast)?;
+        writeln!(w, "Ok(())\n}}")?;
+
+        writeln!(w, "fn wire_size_mapped(&self) -> usize {{")?;
+        writeln!(w, "let wire = XdrMapping::to_xdr(self);")?;
+        match target {
+            // Opaques are a byte slice, with no length prefix or padding of
+            // their own.
+            ArrayType::FixedSize(BasicType::Opaque, _) => {
+                writeln!(w, "wire.wire_size() + pad_length(wire.wire_size())")?
+            }
+            ArrayType::None(BasicType::Opaque) | ArrayType::VariableSize(BasicType::Opaque, _) => {
+                writeln!(w, "4 + wire.wire_size() + pad_length(wire.wire_size())")?
+            }
+            _ => writeln!(w, "wire.wire_size()")?,
+        }
+        writeln!(w, "}}\n}}")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    macro_rules! test_convert {
+        ($name: ident, $input: expr, $mappings: expr, $want: expr) => {
+            #[test]
+            fn $name() {
+                let mappings = $mappings
+                    .iter()
+                    .map(|(k, v): &(&str, &str)| (k.to_string(), v.to_string()))
+                    .collect::<BTreeMap<_, _>>();
+                let ast = Ast::with_mappings($input, &mappings).unwrap();
+
+                let mut got = String::new();
+                print_impl_mappings(&mut got, &ast).unwrap();
+
+                assert_eq!(got, $want);
+            }
+        };
+    }
+
+    test_convert!(
+        test_mapped_typedefs,
+        r#"
+			typedef opaque uuid[16];
+			typedef unsigned int bitmap4<>;
+		"#,
+        [("uuid", "uuid::Uuid"), ("bitmap4", "crate::Bitmap")],
+        r#"impl EncodeMapped for crate::Bitmap {
+fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+let wire = XdrMapping::to_xdr(self);
+buf.write_variable_array(&wire, None)?;
+Ok(())
+}
+fn wire_size_mapped(&self) -> usize {
+let wire = XdrMapping::to_xdr(self);
+wire.wire_size()
+}
+}
+impl EncodeMapped for uuid::Uuid {
+fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+let wire = XdrMapping::to_xdr(self);
+buf.write_bytes(wire.as_ref(), 16)?;
+Ok(())
+}
+fn wire_size_mapped(&self) -> usize {
+let wire = XdrMapping::to_xdr(self);
+wire.wire_size() + pad_length(wire.wire_size())
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_mapped_struct,
+        r#"
+			struct nfstime4 {
+				hyper seconds;
+				unsigned int nseconds;
+			};
+		"#,
+        [("nfstime4", "std::time::SystemTime")],
+        r#"impl EncodeMapped for std::time::SystemTime {
+fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+let wire = XdrMapping::to_xdr(self);
+wire.encode(buf)?;
+Ok(())
+}
+fn wire_size_mapped(&self) -> usize {
+let wire = XdrMapping::to_xdr(self);
+wire.wire_size()
+}
+}
+"#
+    );
+
+    #[test]
+    fn test_mapping_undefined_type() {
+        let mappings = vec![("missing".to_string(), "crate::Missing".to_string())]
+            .into_iter()
+            .collect();
+
+        let got = Ast::with_mappings("struct a { int b; };", &mappings).unwrap_err();
+        assert_eq!(got.to_string(), "type mapping for undefined type missing");
+    }
+}
@@ -1,14 +1,149 @@
 pub mod template;
 
+mod client;
+pub use client::*;
+
+mod encode;
+pub use encode::*;
+
 mod from;
 pub use from::*;
 
+mod mapping;
+pub use mapping::*;
+
+mod procedures;
+pub use procedures::*;
+
+mod attributes;
+pub use attributes::*;
+
 mod types;
 pub use types::*;
 
+mod server;
+pub use server::*;
+
 mod wire_size;
 pub use wire_size::*;
 
+use crate::ast::{ArraySize, ArrayType, Ast, BasicType};
+
+/// A length-checked runtime newtype, used in place of a bounded `string<N>`,
+/// a bounded `opaque<N>` or a fixed size `opaque[N]` when generating bounded
+/// types.
+pub(crate) struct BoundedType<'a> {
+    pub(crate) name: &'static str,
+    size: &'a ArraySize,
+}
+
+impl<'a> BoundedType<'a> {
+    /// Returns the newtype used for values of type `t`, or `None` if `t` is
+    /// not a bounded string or opaque.
+    pub(crate) fn new(t: &'a ArrayType<BasicType>) -> Option<Self> {
+        let (name, size) = match t {
+            ArrayType::VariableSize(BasicType::String, Some(s)) => ("BoundedString", s),
+            ArrayType::VariableSize(BasicType::Opaque, Some(s)) => ("BoundedOpaque", s),
+            ArrayType::FixedSize(BasicType::Opaque, s) => ("FixedOpaque", s),
+            _ => return None,
+        };
+        Some(Self { name, size })
+    }
+}
+
+impl std::fmt::Display for BoundedType<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Constant sizes are cast to the usize const parameter in a block.
+        let size = match self.size {
+            ArraySize::Known(s) => s.to_string(),
+            ArraySize::Constant(_) => format!("{{ {} }}", self.size),
+        };
+
+        match self.name {
+            "BoundedString" => write!(f, "{}<{}>", self.name, size),
+            _ => write!(f, "{}<T, {}>", self.name, size),
+        }
+    }
+}
+
+/// The generic parameter of the types in the `GenericIndex` - the opaque type
+/// `T`, or the lifetime `'a` of the buffer borrowed types are decoded from.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Generics {
+    borrowed: bool,
+}
+
+impl Generics {
+    pub(crate) fn new(ast: &Ast) -> Self {
+        Self {
+            borrowed: ast.is_borrowed(),
+        }
+    }
+
+    /// Returns the generic parameter, `T` or `'a`.
+    pub(crate) fn param(&self) -> &'static str {
+        match self.borrowed {
+            true => "'a",
+            false => "T",
+        }
+    }
+
+    /// Returns the where clause bounding the parameter, if any, with a
+    /// leading space.
+    pub(crate) fn where_clause(&self) -> &'static str {
+        match self.borrowed {
+            true => "",
+            false => " where T: AsRef<[u8]> + Debug",
+        }
+    }
+
+    /// Returns the parameter list declaring the bounds inline.
+    pub(crate) fn bounded_params(&self) -> &'static str {
+        match self.borrowed {
+            true => "<'a>",
+            false => "<T: AsRef<[u8]> + Debug>",
+        }
+    }
+
+    /// Returns the Rust type of opaque data.
+    pub(crate) fn opaque(&self) -> &'static str {
+        match self.borrowed {
+            true => "&'a [u8]",
+            false => "T",
+        }
+    }
+
+    /// Returns the Rust type of a string.
+    pub(crate) fn string(&self) -> &'static str {
+        match self.borrowed {
+            true => "&'a str",
+            false => "String",
+        }
+    }
+}
+
+/// Returns the Rust type of a decoded union discriminant of type `t`.
+///
+/// Typedefs are resolved one level down the typedef chain, matching the type
+/// read by the generated `TryFrom` implementations.
+pub(crate) fn switch_type(ast: &Ast, t: &BasicType) -> BasicType {
+    match t {
+        BasicType::Ident(i) => ast
+            .types()
+            .typedef_target(i)
+            .map(|v| v.target.clone())
+            .unwrap_or_else(|| t.clone()),
+        _ => t.clone(),
+    }
+}
+
+/// Returns true if `t` is an external type replacing an XDR type, which is
+/// encoded with the generated `EncodeMapped` trait rather than the runtime
+/// `Encode` and `WireSize` traits.
+pub(crate) fn is_mapped(ast: &Ast, t: &BasicType) -> bool {
+    matches!(t, BasicType::Ident(i) if ast.mappings().source(i).is_some())
+}
+
 pub(crate) struct SafeName<T>(T)
 where
     T: AsRef<str>;
@@ -48,13 +183,18 @@ where
     T: AsRef<str>,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(c) = self.0.as_ref().chars().next() {
+        let v = self.0.as_ref();
+        if let Some(negative) = v.strip_prefix('-') {
+            return write!(f, "v_neg_{}", negative);
+        }
+
+        if let Some(c) = v.chars().next() {
             if c.is_numeric() {
                 write!(f, "v_")?;
             }
         }
 
-        write!(f, "{}", self.0.as_ref())
+        write!(f, "{}", v)
     }
 }
 
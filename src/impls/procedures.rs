@@ -0,0 +1,337 @@
+use super::from::{print_decode_array, TypeResolve};
+use super::{Generics, SafeName};
+use crate::ast::{indexes::*, ArrayType, Ast, BasicType, Procedure, Version};
+use crate::impls::template::*;
+use crate::Result;
+
+/// `Message` identifies the direction of an ONC RPC message, selecting the
+/// procedure types carried by the generated dispatch enum.
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Message {
+    Call,
+    Reply,
+}
+
+impl Message {
+    pub(crate) fn all() -> [Message; 2] {
+        [Message::Call, Message::Reply]
+    }
+
+    /// Returns the name of the generated dispatch enum for `version`.
+    pub(crate) fn type_name(&self, version: &Version) -> String {
+        match self {
+            Self::Call => format!("{}Call", version.name()),
+            Self::Reply => format!("{}Reply", version.name()),
+        }
+    }
+
+    /// Returns the types carried by a message for procedure `p` - the
+    /// arguments for a call, and the result (if any) for a reply.
+    pub(crate) fn types<'a>(&self, p: &'a Procedure) -> Vec<&'a BasicType> {
+        match self {
+            Self::Call => p.args.iter().collect(),
+            Self::Reply => p.result.iter().collect(),
+        }
+    }
+
+    /// Returns the path of the generated dispatch enum for `version`, naming
+    /// the template type for generic enums, as it cannot be inferred for
+    /// procedures that do not carry opaque data.
+    pub(crate) fn type_path<T: FromTemplate>(
+        &self,
+        version: &Version,
+        ast: &Ast,
+        template: T,
+    ) -> String {
+        match self.is_generic(version, ast) {
+            true => format!("{}::<{}>", self.type_name(version), template.type_name()),
+            false => self.type_name(version),
+        }
+    }
+
+    /// Returns true if any of the procedure types in `version` require a
+    /// generic `AsRef<[u8]>` bound, or borrow from the buffer.
+    pub(crate) fn is_generic(&self, version: &Version, ast: &Ast) -> bool {
+        version
+            .procedures
+            .iter()
+            .flat_map(|p| self.types(p))
+            .any(|t| match t {
+                BasicType::Opaque => true,
+                BasicType::String => ast.is_borrowed(),
+                BasicType::Ident(i) => ast.generics().contains(i.as_str()),
+                _ => false,
+            })
+    }
+}
+
+/// Prints the procedure dispatch helpers for the generated `Call` and `Reply`
+/// enums of each program version.
+///
+/// A `decode()` function is generated to decode a message body given the
+/// procedure number, and a `procedure()` method to map a message to its
+/// procedure number.
+pub fn print_impl_procedures<W: std::fmt::Write, T: FromTemplate>(
+    mut w: W,
+    template: T,
+    ast: &Ast,
+) -> Result<()> {
+    let generics = Generics::new(ast);
+
+    for program in ast.emitted_programs() {
+        for version in program.versions.iter() {
+            for msg in Message::all().iter() {
+                let name = msg.type_name(version);
+                let generic = msg.is_generic(version, ast);
+
+                // Resolve all the procedure numbers up front.
+                let numbers = version
+                    .procedures
+                    .iter()
+                    .map(|p| resolve_number(&p.number, ast))
+                    .collect::<Result<Vec<_>>>()?;
+
+                // The procedure number mapping is independent of the decode
+                // template, so implement it for all T.
+                if generic {
+                    writeln!(
+                        w,
+                        "impl<{p}> {}<{p}>{} {{",
+                        name,
+                        generics.where_clause(),
+                        p = generics.param()
+                    )?;
+                } else {
+                    writeln!(w, "impl {} {{", name)?;
+                }
+
+                writeln!(w, "pub fn procedure(&self) -> u32 {{\nmatch self {{")?;
+                for (p, number) in version.procedures.iter().zip(numbers.iter()) {
+                    let fields = if msg.types(p).is_empty() { "" } else { "(..)" };
+                    writeln!(w, "Self::{}{} => {},", SafeName(&p.name), fields, number)?;
+                }
+                writeln!(w, "}}\n}}")?;
+
+                // Generic messages are only decoded into the template's type,
+                // while others declare any lifetime of the buffer on decode().
+                let mut decode_generics = template.impl_generics();
+                if generic {
+                    writeln!(
+                        w,
+                        "}}\nimpl{} {}<{}> {{",
+                        decode_generics,
+                        name,
+                        template.type_name()
+                    )?;
+                    decode_generics = "";
+                }
+
+                writeln!(
+                    w,
+                    "pub fn decode{}(procedure: u32, mut v: {}) -> Result<Self, Error> {{",
+                    decode_generics,
+                    template.try_from()
+                )?;
+                writeln!(w, "Ok(match procedure {{")?;
+                for (p, number) in version.procedures.iter().zip(numbers.iter()) {
+                    write!(w, "{} => Self::{}", number, SafeName(&p.name))?;
+
+                    let types = msg.types(p);
+                    if !types.is_empty() {
+                        write!(w, "(")?;
+                        for (i, t) in types.into_iter().enumerate() {
+                            if i > 0 {
+                                write!(w, ", ")?;
+                            }
+                            print_decode_array(
+                                &mut w,
+                                template,
+                                &ArrayType::None(t.clone()),
+                                ast,
+                                TypeResolve::UseAlias,
+                                template.ref_type(),
+                            )?;
+                        }
+                        write!(w, ")")?;
+                    }
+                    writeln!(w, ",")?;
+                }
+                writeln!(w, "p => return Err(Error::UnknownProcedure(p)),")?;
+                writeln!(w, "}})\n}}\n}}")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves a program, version or procedure number to a literal value, looking
+/// up named constants in the constant index.
+pub(crate) fn resolve_number(number: &str, ast: &Ast) -> Result<String> {
+    match ast.constants().get(number) {
+        Some(ConstantType::ConstValue(v)) => Ok(v.to_string()),
+        Some(ConstantType::EnumValue { .. }) => {
+            Err(format!("{} is an enum variant, not a constant", number).into())
+        }
+        None if number.starts_with(|c: char| c.is_ascii_digit()) => Ok(number.to_string()),
+        None => Err(format!("unknown constant {}", number).into()),
+    }
+}
+
+/// Returns the name of the generated client and service trait methods for
+/// procedure `p`.
+pub(crate) fn method_name(p: &Procedure) -> String {
+    SafeName(p.name.to_lowercase()).to_string()
+}
+
+/// Prints the Rust type of a procedure argument or result, using the template
+/// type for opaque data.
+pub(crate) fn print_message_type<W: std::fmt::Write, T: FromTemplate>(
+    w: &mut W,
+    template: T,
+    t: &BasicType,
+    ast: &Ast,
+) -> Result<()> {
+    match t {
+        BasicType::Opaque => write!(w, "{}", template.type_name())?,
+        BasicType::String => write!(w, "String")?,
+        BasicType::Ident(i) if ast.generics().contains(i.as_ref()) => {
+            write!(w, "{}<{}>", i, template.type_name())?
+        }
+        _ => write!(w, "{}", t)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bytes::Bytes;
+    use super::*;
+
+    macro_rules! test_convert {
+        ($name: ident, $input: expr, $want: expr) => {
+            #[test]
+            fn $name() {
+                let ast = Ast::new($input).unwrap();
+
+                let mut got = String::new();
+                print_impl_procedures(&mut got, Bytes, &ast).unwrap();
+
+                assert_eq!(got, $want);
+            }
+        };
+    }
+
+    test_convert!(
+        test_program,
+        r#"
+            const PROC_GETATTR = 1;
+            struct GETATTR3args {
+                uint32_t a;
+            };
+            struct GETATTR3res {
+                uint32_t b;
+            };
+            program NFS_PROGRAM {
+                version NFS_V3 {
+                    void NFSPROC3_NULL(void) = 0;
+                    GETATTR3res NFSPROC3_GETATTR(GETATTR3args) = PROC_GETATTR;
+                    void NFSPROC3_MULTI(unsigned int, string) = 0x10;
+                } = 3;
+            } = 100003;
+        "#,
+        r#"impl NFS_V3Call {
+pub fn procedure(&self) -> u32 {
+match self {
+Self::NFSPROC3_NULL => 0,
+Self::NFSPROC3_GETATTR(..) => 1,
+Self::NFSPROC3_MULTI(..) => 0x10,
+}
+}
+pub fn decode(procedure: u32, mut v: Bytes) -> Result<Self, Error> {
+Ok(match procedure {
+0 => Self::NFSPROC3_NULL,
+1 => Self::NFSPROC3_GETATTR(GETATTR3args::try_from(&mut v)?),
+0x10 => Self::NFSPROC3_MULTI(v.read_u32()?, v.read_string(None)?),
+p => return Err(Error::UnknownProcedure(p)),
+})
+}
+}
+impl NFS_V3Reply {
+pub fn procedure(&self) -> u32 {
+match self {
+Self::NFSPROC3_NULL => 0,
+Self::NFSPROC3_GETATTR(..) => 1,
+Self::NFSPROC3_MULTI => 0x10,
+}
+}
+pub fn decode(procedure: u32, mut v: Bytes) -> Result<Self, Error> {
+Ok(match procedure {
+0 => Self::NFSPROC3_NULL,
+1 => Self::NFSPROC3_GETATTR(GETATTR3res::try_from(&mut v)?),
+0x10 => Self::NFSPROC3_MULTI,
+p => return Err(Error::UnknownProcedure(p)),
+})
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_program_generic,
+        r#"
+            typedef opaque fhandle<>;
+            program P {
+                version V1 {
+                    fhandle LOOKUP(string) = 1;
+                } = 1;
+            } = 1;
+        "#,
+        r#"impl V1Call {
+pub fn procedure(&self) -> u32 {
+match self {
+Self::LOOKUP(..) => 1,
+}
+}
+pub fn decode(procedure: u32, mut v: Bytes) -> Result<Self, Error> {
+Ok(match procedure {
+1 => Self::LOOKUP(v.read_string(None)?),
+p => return Err(Error::UnknownProcedure(p)),
+})
+}
+}
+impl<T> V1Reply<T> where T: AsRef<[u8]> + Debug {
+pub fn procedure(&self) -> u32 {
+match self {
+Self::LOOKUP(..) => 1,
+}
+}
+}
+impl V1Reply<Bytes> {
+pub fn decode(procedure: u32, mut v: Bytes) -> Result<Self, Error> {
+Ok(match procedure {
+1 => Self::LOOKUP(fhandle::try_from(&mut v)?),
+p => return Err(Error::UnknownProcedure(p)),
+})
+}
+}
+"#
+    );
+
+    #[test]
+    fn test_unknown_procedure_constant() {
+        let ast = Ast::new(
+            r#"
+            program P {
+                version V1 {
+                    void NULL(void) = UNDEFINED;
+                } = 1;
+            } = 1;
+        "#,
+        )
+        .unwrap();
+
+        let mut got = String::new();
+        assert!(print_impl_procedures(&mut got, Bytes, &ast).is_err());
+    }
+}
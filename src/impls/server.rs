@@ -0,0 +1,217 @@
+use super::procedures::{method_name, print_message_type, resolve_number, Message};
+use super::SafeName;
+use crate::ast::Ast;
+use crate::impls::template::*;
+use crate::Result;
+
+/// Prints a service trait for each program version, with a method per
+/// procedure, and a server implementing `server::Dispatch` for any
+/// implementation of it.
+///
+/// The server decodes the `Call` message for the procedure, invokes the
+/// service method and encodes the `Reply` message with its result.
+pub fn print_services<W: std::fmt::Write, T: FromTemplate>(
+    mut w: W,
+    template: T,
+    ast: &Ast,
+) -> Result<()> {
+    for program in ast.emitted_programs() {
+        let program_number = resolve_number(&program.number, ast)?;
+
+        for version in program.versions.iter() {
+            let service = format!("{}Service", version.name());
+            let server = format!("{}Server", version.name());
+            let version_number = resolve_number(&version.number, ast)?;
+
+            writeln!(
+                w,
+                "/// The procedures of version {} of the {} program.",
+                version_number,
+                program.name()
+            )?;
+            writeln!(w, "pub trait {} {{", service)?;
+            for p in version.procedures.iter() {
+                write!(w, "fn {}(&self", method_name(p))?;
+                for (i, t) in p.args.iter().enumerate() {
+                    write!(w, ", v{}: ", i)?;
+                    print_message_type(&mut w, template, t, ast)?;
+                }
+                write!(w, ") -> impl std::future::Future<Output = ")?;
+                match &p.result {
+                    Some(t) => print_message_type(&mut w, template, t, ast)?,
+                    None => write!(w, "()")?,
+                }
+                writeln!(w, "> + Send;")?;
+            }
+            writeln!(w, "}}")?;
+
+            writeln!(
+                w,
+                "/// Dispatches calls to version {} of the {} program to a [`{}`].",
+                version_number,
+                program.name(),
+                service
+            )?;
+            writeln!(w, "#[derive(Debug)]")?;
+            writeln!(w, "pub struct {}<S>(pub S);", server)?;
+
+            writeln!(
+                w,
+                "impl<S: {} + Sync> server::Dispatch for {}<S> {{",
+                service, server
+            )?;
+            writeln!(w, "const PROGRAM: u32 = {};", program_number)?;
+            writeln!(w, "const VERSION: u32 = {};", version_number)?;
+            writeln!(
+                w,
+                "async fn call(&self, procedure: u32, args: Bytes, buf: &mut fastxdr::bytes::BytesMut) -> Result<(), accept_stat> {{"
+            )?;
+            let call = Message::Call.type_name(version);
+            let reply = Message::Reply.type_path(version, ast, template);
+
+            writeln!(w, "let call = match {}::decode(procedure, args) {{", call)?;
+            writeln!(w, "Ok(v) => v,")?;
+            writeln!(
+                w,
+                "Err(Error::UnknownProcedure(_)) => return Err(accept_stat::PROC_UNAVAIL),"
+            )?;
+            writeln!(w, "Err(_) => return Err(accept_stat::GARBAGE_ARGS),")?;
+            writeln!(w, "}};")?;
+
+            writeln!(w, "let reply = match call {{")?;
+            for p in version.procedures.iter() {
+                let args = (0..p.args.len())
+                    .map(|i| format!("v{}", i))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                match args.is_empty() {
+                    true => write!(w, "{}::{} => ", call, SafeName(&p.name))?,
+                    false => write!(w, "{}::{}({}) => ", call, SafeName(&p.name), args)?,
+                }
+
+                let invoke = format!("self.0.{}({}).await", method_name(p), args);
+                match p.result {
+                    Some(_) => writeln!(w, "{}::{}({}),", reply, SafeName(&p.name), invoke)?,
+                    None => writeln!(w, "{{\n{};\n{}::{}\n}}", invoke, reply, SafeName(&p.name))?,
+                }
+            }
+            writeln!(w, "}};")?;
+            writeln!(w, "reply.encode(buf).map_err(|_| accept_stat::SYSTEM_ERR)")?;
+            writeln!(w, "}}\n}}")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bytes::Bytes;
+    use super::*;
+
+    macro_rules! test_convert {
+        ($name: ident, $input: expr, $want: expr) => {
+            #[test]
+            fn $name() {
+                let ast = Ast::new($input).unwrap();
+
+                let mut got = String::new();
+                print_services(&mut got, Bytes, &ast).unwrap();
+
+                assert_eq!(got, $want);
+            }
+        };
+    }
+
+    test_convert!(
+        test_service,
+        r#"
+            const PROC_GETATTR = 1;
+            struct GETATTR3args {
+                uint32_t a;
+            };
+            struct GETATTR3res {
+                uint32_t b;
+            };
+            program NFS_PROGRAM {
+                version NFS_V3 {
+                    void NFSPROC3_NULL(void) = 0;
+                    GETATTR3res NFSPROC3_GETATTR(GETATTR3args) = PROC_GETATTR;
+                    int NFSPROC3_MULTI(unsigned int, string) = 0x10;
+                } = 3;
+            } = 100003;
+        "#,
+        r#"/// The procedures of version 3 of the NFS_PROGRAM program.
+pub trait NFS_V3Service {
+fn nfsproc3_null(&self) -> impl std::future::Future<Output = ()> + Send;
+fn nfsproc3_getattr(&self, v0: GETATTR3args) -> impl std::future::Future<Output = GETATTR3res> + Send;
+fn nfsproc3_multi(&self, v0: u32, v1: String) -> impl std::future::Future<Output = i32> + Send;
+}
+/// Dispatches calls to version 3 of the NFS_PROGRAM program to a [`NFS_V3Service`].
+#[derive(Debug)]
+pub struct NFS_V3Server<S>(pub S);
+impl<S: NFS_V3Service + Sync> server::Dispatch for NFS_V3Server<S> {
+const PROGRAM: u32 = 100003;
+const VERSION: u32 = 3;
+async fn call(&self, procedure: u32, args: Bytes, buf: &mut fastxdr::bytes::BytesMut) -> Result<(), accept_stat> {
+let call = match NFS_V3Call::decode(procedure, args) {
+Ok(v) => v,
+Err(Error::UnknownProcedure(_)) => return Err(accept_stat::PROC_UNAVAIL),
+Err(_) => return Err(accept_stat::GARBAGE_ARGS),
+};
+let reply = match call {
+NFS_V3Call::NFSPROC3_NULL => {
+self.0.nfsproc3_null().await;
+NFS_V3Reply::NFSPROC3_NULL
+}
+NFS_V3Call::NFSPROC3_GETATTR(v0) => NFS_V3Reply::NFSPROC3_GETATTR(self.0.nfsproc3_getattr(v0).await),
+NFS_V3Call::NFSPROC3_MULTI(v0, v1) => NFS_V3Reply::NFSPROC3_MULTI(self.0.nfsproc3_multi(v0, v1).await),
+};
+reply.encode(buf).map_err(|_| accept_stat::SYSTEM_ERR)
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_service_generic,
+        r#"
+            typedef opaque fhandle<>;
+            program P {
+                version V1 {
+                    void NULL(void) = 0;
+                    fhandle LOOKUP(string) = 1;
+                } = 1;
+            } = 0x20000001;
+        "#,
+        r#"/// The procedures of version 1 of the P program.
+pub trait V1Service {
+fn null(&self) -> impl std::future::Future<Output = ()> + Send;
+fn lookup(&self, v0: String) -> impl std::future::Future<Output = fhandle<Bytes>> + Send;
+}
+/// Dispatches calls to version 1 of the P program to a [`V1Service`].
+#[derive(Debug)]
+pub struct V1Server<S>(pub S);
+impl<S: V1Service + Sync> server::Dispatch for V1Server<S> {
+const PROGRAM: u32 = 0x20000001;
+const VERSION: u32 = 1;
+async fn call(&self, procedure: u32, args: Bytes, buf: &mut fastxdr::bytes::BytesMut) -> Result<(), accept_stat> {
+let call = match V1Call::decode(procedure, args) {
+Ok(v) => v,
+Err(Error::UnknownProcedure(_)) => return Err(accept_stat::PROC_UNAVAIL),
+Err(_) => return Err(accept_stat::GARBAGE_ARGS),
+};
+let reply = match call {
+V1Call::NULL => {
+self.0.null().await;
+V1Reply::<Bytes>::NULL
+}
+V1Call::LOOKUP(v0) => V1Reply::<Bytes>::LOOKUP(self.0.lookup(v0).await),
+};
+reply.encode(buf).map_err(|_| accept_stat::SYSTEM_ERR)
+}
+}
+"#
+    );
+}
@@ -1,8 +1,16 @@
 pub mod bytes;
+pub mod slice;
+
 pub trait FromTemplate: Copy {
     fn type_name(&self) -> &'static str;
     fn try_from(&self) -> &'static str;
     fn ref_type(&self) -> ReferenceType;
+
+    /// The generic parameters of the `impl` blocks decoding from the template
+    /// buffer, declaring the lifetime of a borrowed buffer.
+    fn impl_generics(&self) -> &'static str {
+        ""
+    }
 }
 
 /// `ReferenceType` defines how the generated code should pass type instances
@@ -0,0 +1,45 @@
+use super::{FromTemplate, ReferenceType};
+
+/// Decodes types borrowing from a `&'a [u8]` buffer, with opaque data as
+/// `&'a [u8]` and strings as `&'a str`.
+#[derive(Debug, Clone, Copy)]
+pub struct Slice;
+
+impl FromTemplate for Slice {
+    fn type_name(&self) -> &'static str {
+        "'a"
+    }
+
+    fn try_from(&self) -> &'static str {
+        "&'a [u8]"
+    }
+
+    fn ref_type(&self) -> ReferenceType {
+        ReferenceType::ByRef
+    }
+
+    fn impl_generics(&self) -> &'static str {
+        "<'a>"
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RefMutSlice;
+
+impl FromTemplate for RefMutSlice {
+    fn type_name(&self) -> &'static str {
+        "'a"
+    }
+
+    fn try_from(&self) -> &'static str {
+        "&mut &'a [u8]"
+    }
+
+    fn ref_type(&self) -> ReferenceType {
+        ReferenceType::ByValue
+    }
+
+    fn impl_generics(&self) -> &'static str {
+        "<'a>"
+    }
+}
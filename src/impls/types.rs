@@ -1,29 +1,53 @@
-use super::{NonDigitName, SafeName};
-use crate::ast::{indexes::*, ArrayType, Ast, BasicType};
+use super::{
+    switch_type, Attributes, BoundedType, Derivable, Generics, Message, NonDigitName, SafeName,
+    TypeKind,
+};
+use crate::ast::{indexes::*, ArrayType, Ast, BasicType, Enum, UnionCase, VariantValue};
 use crate::Result;
 
-const TRAIT_BOUNDS: &str = "<T> where T: AsRef<[u8]> + Debug";
+/// Prints the constants and type declarations in `ast`, prepending the
+/// matching `attributes` to each type, field and variant.
+///
+/// If `bounded` is true, bounded strings and bounded or fixed size opaques are
+/// declared as the length-checked runtime newtypes.
+pub fn print_types<W: std::fmt::Write>(
+    w: &mut W,
+    ast: &Ast,
+    attributes: &Attributes,
+    bounded: bool,
+) -> Result<()> {
+    let derivable = Derivable::new(ast, attributes, bounded);
+    let generics = Generics::new(ast);
 
-pub fn print_types<W: std::fmt::Write>(w: &mut W, ast: &Ast, derive: &str) -> Result<()> {
-    for item in ast.constants().iter() {
+    for item in ast.emitted_constants() {
         match item.1 {
             ConstantType::EnumValue { .. } => continue,
-            ConstantType::ConstValue(s) => writeln!(w, "pub const {}: u32 = {};", item.0, s)?,
+            ConstantType::ConstValue(v) => {
+                writeln!(w, "pub const {}: {} = {};", item.0, v.rust_type(), v)?
+            }
         }
     }
 
-    for item in ast.types().iter() {
+    for item in ast.emitted_types() {
         match item {
             AstType::Struct(v) => {
-                writeln!(w, "{}", derive)?;
+                let generic = ast.generics().contains(v.name.as_str());
+                attributes.print_type(w, &v.name, TypeKind::Struct, generic, &derivable)?;
                 write!(w, "pub struct {}", v.name)?;
-                if ast.generics().contains(v.name.as_str()) {
-                    write!(w, "{}", TRAIT_BOUNDS)?;
+                if generic {
+                    write!(w, "<{}>{}", generics.param(), generics.where_clause())?;
                 }
 
                 writeln!(w, " {{")?;
                 for f in v.fields.iter() {
-                    write!(w, "pub {}: ", SafeName(&f.field_name))?;
+                    let ident = SafeName(&f.field_name).to_string();
+                    let checked = BoundedType::new(&f.field_value).filter(|_| bounded);
+                    let opaque = checked.is_none()
+                        && !f.is_optional
+                        && *f.field_value.unwrap_array() == BasicType::Opaque;
+
+                    attributes.print_field(w, &v.name, &f.field_name, &ident, opaque)?;
+                    write!(w, "pub {}: ", ident)?;
 
                     // Optional fields require boxing to allow a self-referential
                     // type chain
@@ -32,16 +56,17 @@ pub fn print_types<W: std::fmt::Write>(w: &mut W, ast: &Ast, derive: &str) -> Re
                     }
 
                     // For each field, replace any "opaque" types with T, which will
-                    // be generic for AsRef<[u8]>.
+                    // be generic for AsRef<[u8]> (or borrow the buffer).
                     //
                     // For each ident, check if it is in the generic index, and if
                     // so, append <T> for the AsRef.
-                    match f.field_value.unwrap_array() {
-                        BasicType::Opaque => write!(w, "T")?,
-                        BasicType::String => write!(w, "String")?,
-                        BasicType::Ident(i) if ast.generics().contains(i.as_ref()) => {
+                    match (&checked, f.field_value.unwrap_array()) {
+                        (Some(c), _) => write!(w, "{}", c)?,
+                        (None, BasicType::Opaque) => write!(w, "{}", generics.opaque())?,
+                        (None, BasicType::String) => write!(w, "{}", generics.string())?,
+                        (None, BasicType::Ident(i)) if ast.generics().contains(i.as_ref()) => {
                             f.field_value
-                                .write_with_bounds(w, Some(vec!["T"].as_ref()))?;
+                                .write_with_bounds(w, Some(vec![generics.param()].as_ref()))?;
                         }
                         _ => write!(w, "{}", f.field_value)?,
                     }
@@ -55,13 +80,30 @@ pub fn print_types<W: std::fmt::Write>(w: &mut W, ast: &Ast, derive: &str) -> Re
                 writeln!(w, "}}")?;
             }
             AstType::Union(v) => {
-                writeln!(w, "{}", derive)?;
+                let generic = ast.generics().contains(v.name());
+                attributes.print_type(w, v.name(), TypeKind::Union, generic, &derivable)?;
                 write!(w, "pub enum {}", v.name())?;
-                if ast.generics().contains(v.name()) {
-                    write!(w, "{}", TRAIT_BOUNDS)?;
+                if generic {
+                    write!(w, "<{}>{}", generics.param(), generics.where_clause())?;
                 }
 
                 writeln!(w, " {{")?;
+
+                let write_case_type = |w: &mut W, case: &UnionCase| -> Result<()> {
+                    match case.field_value.unwrap_array() {
+                        BasicType::Opaque => {
+                            attributes.print_opaque(w)?;
+                            write!(w, "{}", generics.opaque())?
+                        }
+                        BasicType::String => write!(w, "{}", generics.string())?,
+                        BasicType::Ident(i) if ast.generics().contains(i.as_ref()) => {
+                            write!(w, "{}<{}>", i, generics.param())?
+                        }
+                        _ => write!(w, "{}", case.field_value)?,
+                    }
+                    Ok(())
+                };
+
                 for case in v.cases.iter() {
                     // A single case statement may have many case values tied to it
                     // if fallthrough values are used:
@@ -71,40 +113,47 @@ pub fn print_types<W: std::fmt::Write>(w: &mut W, ast: &Ast, derive: &str) -> Re
                     // 		// statement
                     //
                     for c_value in case.case_values.iter() {
-                        write!(w, "{}(", NonDigitName(SafeName(&c_value)))?;
-
-                        match case.field_value.unwrap_array() {
-                            BasicType::Opaque => write!(w, "T")?,
-                            BasicType::String => write!(w, "String")?,
-                            BasicType::Ident(i) if ast.generics().contains(i.as_ref()) => {
-                                write!(w, "{}<T>", i)?
-                            }
-                            _ => write!(w, "{}", case.field_value)?,
-                        }
-
+                        let ident = NonDigitName(SafeName(&c_value)).to_string();
+                        attributes.print_field(w, v.name(), c_value, &ident, false)?;
+                        write!(w, "{}(", ident)?;
+                        write_case_type(w, case)?;
                         writeln!(w, "),")?;
                     }
                 }
 
+                // The default variant carries the discriminant value read from
+                // the wire so it can be encoded again.
+                let switch = switch_type(ast, &v.switch.var_type);
+
                 // There may also be several "void" cases
                 for c in v.void_cases.iter() {
-                    writeln!(w, "{},", NonDigitName(SafeName(c.as_str())))?;
+                    let ident = NonDigitName(SafeName(c)).to_string();
+                    attributes.print_field(w, v.name(), c, &ident, false)?;
+                    match c.as_str() {
+                        "default" => writeln!(w, "default({}),", switch)?,
+                        _ => writeln!(w, "{},", ident)?,
+                    }
                 }
 
-                if v.default.is_some() {
-                    writeln!(w, "default,")?;
+                if let Some(ref d) = v.default {
+                    attributes.print_field(w, v.name(), "default", "default", false)?;
+                    write!(w, "default({}, ", switch)?;
+                    write_case_type(w, d)?;
+                    writeln!(w, "),")?;
                 }
 
                 writeln!(w, "}}")?;
             }
             AstType::Enum(v) => {
-                writeln!(w, "{}", derive)?;
-                writeln!(w, "#[repr(u32)]")?;
+                attributes.print_type(w, &v.name, TypeKind::Enum, false, &derivable)?;
+                writeln!(w, "#[repr(i32)]")?;
                 writeln!(w, "pub enum {} {{", v.name)?;
                 for var in v.variants.iter() {
-                    writeln!(w, "{} = {},", var.name, var.value)?;
+                    attributes.print_field(w, &v.name, &var.name, &var.name, false)?;
+                    writeln!(w, "{} = {},", var.name, enum_value(&var.value, ast))?;
                 }
                 writeln!(w, "}}")?;
+                print_enum_conversions(w, v)?;
             }
             AstType::Typedef(v) => {
                 // No typedefs to self - this occurs because the ident/type values
@@ -113,6 +162,15 @@ pub fn print_types<W: std::fmt::Write>(w: &mut W, ast: &Ast, derive: &str) -> Re
                     continue;
                 }
 
+                // Mapped typedefs are replaced by the external type.
+                if ast
+                    .mappings()
+                    .get(v.alias.unwrap_array().as_str())
+                    .is_some()
+                {
+                    continue;
+                }
+
                 // For typedefs, the array identifier is defined on the alias.
                 //
                 // Wrap the target in the same array as the alias to generate the
@@ -123,26 +181,35 @@ pub fn print_types<W: std::fmt::Write>(w: &mut W, ast: &Ast, derive: &str) -> Re
                     ArrayType::VariableSize(_, s) => ArrayType::VariableSize(&v.target, s.clone()),
                 };
 
-                writeln!(w, "{}", derive)?;
-                write!(w, "pub struct {}", v.alias.unwrap_array().as_str())?;
-                if ast.generics().contains(v.target.as_str()) || v.target.is_opaque() {
-                    write!(
-                        w,
-                        "<{}>",
-                        TRAIT_BOUNDS.split("where").nth(1).unwrap_or("").trim()
-                    )?;
+                let name = v.alias.unwrap_array().as_str();
+                let generic = ast.generics().contains(name);
+                attributes.print_type(w, name, TypeKind::Typedef, generic, &derivable)?;
+                write!(w, "pub struct {}", name)?;
+                if generic {
+                    write!(w, "{}", generics.bounded_params())?;
                 }
 
-                // If the target is the opaque type, it should not have array
-                // quantifiers - the opaque type has a variable length already.
+                if let Some(b) = BoundedType::new(&v.target_array()).filter(|_| bounded) {
+                    writeln!(w, "(pub {});", b)?;
+                    continue;
+                }
+
+                // If the target is the opaque or string type, it should not
+                // have array quantifiers - they have a variable length already.
                 if v.target.is_opaque() {
-                    writeln!(w, "(pub T);")?;
+                    write!(w, "(")?;
+                    attributes.print_opaque(w)?;
+                    writeln!(w, "pub {});", generics.opaque())?;
+                    continue;
+                }
+                if v.target == BasicType::String {
+                    writeln!(w, "(pub {});", generics.string())?;
                     continue;
                 }
 
                 if ast.generics().contains(v.target.as_str()) {
                     write!(w, " (pub ")?;
-                    target.write_with_bounds(w, Some(&["T"]))?;
+                    target.write_with_bounds(w, Some(&[generics.param()]))?;
                     writeln!(w, ");")?;
                 } else {
                     writeln!(w, "(pub {});", target)?;
@@ -151,6 +218,100 @@ pub fn print_types<W: std::fmt::Write>(w: &mut W, ast: &Ast, derive: &str) -> Re
         };
     }
 
+    // Generate the typed Call and Reply messages for each RPC program version,
+    // with a variant per procedure.
+    for program in ast.emitted_programs() {
+        for version in program.versions.iter() {
+            for msg in Message::all().iter() {
+                let name = msg.type_name(version);
+                let generic = msg.is_generic(version, ast);
+                attributes.print_type(w, &name, TypeKind::Message, generic, &derivable)?;
+                write!(w, "pub enum {}", name)?;
+                if generic {
+                    write!(w, "<{}>{}", generics.param(), generics.where_clause())?;
+                }
+
+                writeln!(w, " {{")?;
+                for p in version.procedures.iter() {
+                    let ident = SafeName(&p.name).to_string();
+                    attributes.print_field(w, &name, &p.name, &ident, false)?;
+                    write!(w, "{}", ident)?;
+
+                    let types = msg.types(p);
+                    if !types.is_empty() {
+                        write!(w, "(")?;
+                        for (i, t) in types.into_iter().enumerate() {
+                            if i > 0 {
+                                write!(w, ", ")?;
+                            }
+                            match t {
+                                BasicType::Opaque => {
+                                    attributes.print_opaque(w)?;
+                                    write!(w, "{}", generics.opaque())?
+                                }
+                                BasicType::String => write!(w, "{}", generics.string())?,
+                                BasicType::Ident(i) if ast.generics().contains(i.as_ref()) => {
+                                    write!(w, "{}<{}>", i, generics.param())?
+                                }
+                                _ => write!(w, "{}", t)?,
+                            }
+                        }
+                        write!(w, ")")?;
+                    }
+                    writeln!(w, ",")?;
+                }
+                writeln!(w, "}}")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the discriminant expression of an enum variant with value `v`.
+///
+/// Constants may be of any integer type, and are cast to the `i32`
+/// representation of the enum.
+fn enum_value(v: &VariantValue, ast: &Ast) -> String {
+    match v {
+        VariantValue::Numeric(n) => n.to_string(),
+        VariantValue::String(c) => match ast.constants().get(c) {
+            Some(ConstantType::EnumValue { enum_name, variant }) => {
+                format!("{}::{} as i32", enum_name, variant)
+            }
+            _ => format!("{} as i32", c),
+        },
+    }
+}
+
+/// Prints the conversions between an enum and its `i32` value.
+fn print_enum_conversions<W: std::fmt::Write>(w: &mut W, v: &Enum) -> Result<()> {
+    writeln!(w, "impl {} {{", v.name)?;
+    writeln!(w, "pub const fn value(&self) -> i32 {{\nmatch self {{")?;
+    for var in v.variants.iter() {
+        writeln!(w, "Self::{0} => Self::{0} as i32,", var.name)?;
+    }
+    writeln!(w, "}}\n}}\n}}")?;
+
+    writeln!(w, "impl From<{}> for i32 {{", v.name)?;
+    writeln!(w, "fn from(v: {}) -> i32 {{\nv.value()\n}}\n}}", v.name)?;
+
+    writeln!(w, "impl TryFrom<i32> for {} {{", v.name)?;
+    writeln!(w, "type Error = Error;\n")?;
+    writeln!(
+        w,
+        "fn try_from(v: i32) -> Result<Self, Self::Error> {{\nOk(match v {{"
+    )?;
+    for var in v.variants.iter() {
+        match var.value {
+            VariantValue::Numeric(n) => writeln!(w, "{} => Self::{},", n, var.name)?,
+            VariantValue::String(_) => {
+                writeln!(w, "c if c == Self::{0} as i32 => Self::{0},", var.name)?
+            }
+        }
+    }
+    writeln!(w, "d => return Err(Error::UnknownVariant(d)),\n}})\n}}\n}}")?;
+
     Ok(())
 }
 
@@ -158,20 +319,212 @@ pub fn print_types<W: std::fmt::Write>(w: &mut W, ast: &Ast, derive: &str) -> Re
 mod tests {
     use super::*;
 
+    use crate::impls::OpaqueFormat;
+
     macro_rules! test_convert {
         ($name: ident, $input: expr, $want: expr) => {
+            test_convert!($name, $input, $want, false);
+        };
+        ($name: ident, $input: expr, $want: expr, $bounded: expr) => {
+            test_convert!(
+                $name,
+                $input,
+                $want,
+                $bounded,
+                Attributes::new("#[derive(Debug, PartialEq)]")
+            );
+        };
+        ($name: ident, $input: expr, $want: expr, $bounded: expr, $attributes: expr) => {
             #[test]
             fn $name() {
                 let ast = Ast::new($input).unwrap();
 
                 let mut got = String::new();
-                print_types(&mut got, &ast, "#[derive(Debug, PartialEq)]").unwrap();
+                print_types(&mut got, &ast, &$attributes, $bounded).unwrap();
 
                 assert_eq!(got, $want);
             }
         };
     }
 
+    test_convert!(
+        test_struct_c_types,
+        r#"
+			struct small {
+				quadruple a;
+				unsigned char b;
+				short c;
+				bool d;
+			};
+		"#,
+        r#"#[derive(Debug, PartialEq)]
+pub struct small {
+pub a: Quadruple,
+pub b: u32,
+pub c: i32,
+pub d: bool,
+}
+"#
+    );
+
+    test_convert!(
+        test_type_derive,
+        r#"
+			struct point {
+				float x;
+				int y;
+			};
+			struct id {
+				int v;
+			};
+			union shape switch (int kind) {
+				case 1:
+					point p;
+				default:
+					void;
+			};
+		"#,
+        r#"#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash, Default)]
+pub struct id {
+pub v: i32,
+}
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct point {
+pub x: f32,
+pub y: i32,
+}
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum shape {
+v_1(point),
+default(i32),
+}
+"#,
+        false,
+        Attributes::new("#[derive(Debug, PartialEq)]")
+            .with_type_derive("*", "Clone, Copy, Eq, Hash")
+            .with_type_derive(TypeKind::Struct, "Default, Eq")
+    );
+
+    test_convert!(
+        test_type_attributes,
+        r#"
+			struct point {
+				int x;
+				int y;
+			};
+			enum kind {
+				A = 1,
+				B = 2
+			};
+			union shape switch (kind k) {
+				case A:
+					point p;
+				case B:
+					void;
+			};
+		"#,
+        r#"#[derive(Debug)]
+#[serde(rename_all = "lowercase")]
+#[repr(i32)]
+pub enum kind {
+#[serde(rename = "a")]
+A = 1,
+B = 2,
+}
+impl kind {
+pub const fn value(&self) -> i32 {
+match self {
+Self::A => Self::A as i32,
+Self::B => Self::B as i32,
+}
+}
+}
+impl From<kind> for i32 {
+fn from(v: kind) -> i32 {
+v.value()
+}
+}
+impl TryFrom<i32> for kind {
+type Error = Error;
+
+fn try_from(v: i32) -> Result<Self, Self::Error> {
+Ok(match v {
+1 => Self::A,
+2 => Self::B,
+d => return Err(Error::UnknownVariant(d)),
+})
+}
+}
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct point {
+#[serde(rename = "a")]
+pub x: i32,
+pub y: i32,
+}
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum shape {
+#[serde(rename = "a")]
+A(point),
+#[serde(skip)]
+B,
+}
+"#,
+        false,
+        Attributes::new("#[derive(Debug)]")
+            .with_type_attribute("p*", "#[non_exhaustive]")
+            .with_type_attribute("shape", "#[non_exhaustive]")
+            .with_type_attribute(TypeKind::Enum, r#"#[serde(rename_all = "lowercase")]"#)
+            .with_field_attribute("point.x", r#"#[serde(rename = "a")]"#)
+            .with_field_attribute("A", r#"#[serde(rename = "a")]"#)
+            .with_field_attribute("sha*.B", "#[serde(skip)]")
+    );
+
+    test_convert!(
+        test_typedef_string,
+        r#"
+			typedef string name<255>;
+		"#,
+        r#"#[derive(Debug, PartialEq)]
+pub struct name(pub String);
+"#
+    );
+
+    test_convert!(
+        test_bounded,
+        r#"
+			const MAXNAME = 8;
+			typedef string name<MAXNAME>;
+			typedef opaque fixed[4];
+			typedef opaque var<>;
+			struct small {
+				string a<4>;
+				opaque b[3];
+				opaque c<MAXNAME>;
+				string d<>;
+				opaque e<>;
+			};
+		"#,
+        r#"pub const MAXNAME: u32 = 8;
+#[derive(Debug, PartialEq)]
+pub struct fixed<T: AsRef<[u8]> + Debug>(pub FixedOpaque<T, 4>);
+#[derive(Debug, PartialEq)]
+pub struct name(pub BoundedString<{ MAXNAME as usize }>);
+#[derive(Debug, PartialEq)]
+pub struct small<T> where T: AsRef<[u8]> + Debug {
+pub a: BoundedString<4>,
+pub b: FixedOpaque<T, 3>,
+pub c: BoundedOpaque<T, { MAXNAME as usize }>,
+pub d: String,
+pub e: T,
+}
+#[derive(Debug, PartialEq)]
+pub struct var<T: AsRef<[u8]> + Debug>(pub T);
+"#,
+        true
+    );
+
     test_convert!(
         test_union,
         r#"
@@ -206,7 +559,7 @@ FALSE(exist_lock_owner4),
 pub enum LOCKT4res {
 NFS4ERR_DENIED(LOCK4denied),
 NFS4_OK,
-default,
+default(nfsstat4),
 }
 "#
     );
@@ -236,7 +589,7 @@ NF4CHR(specdata4),
 NF4SOCK,
 NF4FIFO,
 NF4DIR,
-default,
+default(nfs_ftype4),
 }
 "#
     );
@@ -415,11 +768,35 @@ pub r_addr: String,
 			};
 		"#,
         r#"#[derive(Debug, PartialEq)]
-#[repr(u32)]
+#[repr(i32)]
 pub enum opentype4 {
 OPEN4_NOCREATE = 0,
 OPEN4_CREATE = 1,
 }
+impl opentype4 {
+pub const fn value(&self) -> i32 {
+match self {
+Self::OPEN4_NOCREATE => Self::OPEN4_NOCREATE as i32,
+Self::OPEN4_CREATE => Self::OPEN4_CREATE as i32,
+}
+}
+}
+impl From<opentype4> for i32 {
+fn from(v: opentype4) -> i32 {
+v.value()
+}
+}
+impl TryFrom<i32> for opentype4 {
+type Error = Error;
+
+fn try_from(v: i32) -> Result<Self, Self::Error> {
+Ok(match v {
+0 => Self::OPEN4_NOCREATE,
+1 => Self::OPEN4_CREATE,
+d => return Err(Error::UnknownVariant(d)),
+})
+}
+}
 "#
     );
 
@@ -432,6 +809,23 @@ OPEN4_CREATE = 1,
 "#
     );
 
+    test_convert!(
+        test_const_literals,
+        r#"
+			const NEGATIVE = -1;
+			const OCTAL = 0755;
+			const LARGE = 4294967296;
+			const NEGATIVE_LARGE = -0x80000001;
+			const MAXFILELEN = 0xffffffffffffffff;
+		"#,
+        r#"pub const LARGE: i64 = 4294967296;
+pub const MAXFILELEN: u64 = 0xffffffffffffffff;
+pub const NEGATIVE: i32 = -1;
+pub const NEGATIVE_LARGE: i64 = -0x80000001;
+pub const OCTAL: u32 = 0o755;
+"#
+    );
+
     test_convert!(
         test_enum_const,
         r#"
@@ -446,10 +840,105 @@ OPEN4_CREATE = 1,
         r#"pub const OPEN4CREATE: u32 = 1;
 pub const OPEN4NOCREATE: u32 = 0;
 #[derive(Debug, PartialEq)]
-#[repr(u32)]
+#[repr(i32)]
 pub enum opentype4 {
-OPEN4_NOCREATE = OPEN4NOCREATE,
-OPEN4_CREATE = OPEN4CREATE,
+OPEN4_NOCREATE = OPEN4NOCREATE as i32,
+OPEN4_CREATE = OPEN4CREATE as i32,
+}
+impl opentype4 {
+pub const fn value(&self) -> i32 {
+match self {
+Self::OPEN4_NOCREATE => Self::OPEN4_NOCREATE as i32,
+Self::OPEN4_CREATE => Self::OPEN4_CREATE as i32,
+}
+}
+}
+impl From<opentype4> for i32 {
+fn from(v: opentype4) -> i32 {
+v.value()
+}
+}
+impl TryFrom<i32> for opentype4 {
+type Error = Error;
+
+fn try_from(v: i32) -> Result<Self, Self::Error> {
+Ok(match v {
+c if c == Self::OPEN4_NOCREATE as i32 => Self::OPEN4_NOCREATE,
+c if c == Self::OPEN4_CREATE as i32 => Self::OPEN4_CREATE,
+d => return Err(Error::UnknownVariant(d)),
+})
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_enum_negative,
+        r#"
+			enum status {
+					OK      = 0,
+					ERR     = -1
+			};
+			enum alias {
+					SAME    = ERR
+			};
+		"#,
+        r#"#[derive(Debug, PartialEq)]
+#[repr(i32)]
+pub enum alias {
+SAME = status::ERR as i32,
+}
+impl alias {
+pub const fn value(&self) -> i32 {
+match self {
+Self::SAME => Self::SAME as i32,
+}
+}
+}
+impl From<alias> for i32 {
+fn from(v: alias) -> i32 {
+v.value()
+}
+}
+impl TryFrom<i32> for alias {
+type Error = Error;
+
+fn try_from(v: i32) -> Result<Self, Self::Error> {
+Ok(match v {
+c if c == Self::SAME as i32 => Self::SAME,
+d => return Err(Error::UnknownVariant(d)),
+})
+}
+}
+#[derive(Debug, PartialEq)]
+#[repr(i32)]
+pub enum status {
+OK = 0,
+ERR = -1,
+}
+impl status {
+pub const fn value(&self) -> i32 {
+match self {
+Self::OK => Self::OK as i32,
+Self::ERR => Self::ERR as i32,
+}
+}
+}
+impl From<status> for i32 {
+fn from(v: status) -> i32 {
+v.value()
+}
+}
+impl TryFrom<i32> for status {
+type Error = Error;
+
+fn try_from(v: i32) -> Result<Self, Self::Error> {
+Ok(match v {
+0 => Self::OK,
+-1 => Self::ERR,
+d => return Err(Error::UnknownVariant(d)),
+})
+}
 }
 "#
     );
@@ -766,6 +1255,35 @@ pub struct alias<T: AsRef<[u8]> + Debug>(pub T);
 "#
     );
 
+    test_convert!(
+        test_program_messages,
+        r#"
+            typedef opaque fhandle<>;
+            program NFS_PROGRAM {
+                version NFS_V3 {
+                    void NFSPROC3_NULL(void) = 0;
+                    fhandle NFSPROC3_LOOKUP(string, unsigned int) = 1;
+                    bool NFSPROC3_CHECK(fhandle) = 2;
+                } = 3;
+            } = 100003;
+		"#,
+        r#"#[derive(Debug, PartialEq)]
+pub struct fhandle<T: AsRef<[u8]> + Debug>(pub T);
+#[derive(Debug, PartialEq)]
+pub enum NFS_V3Call<T> where T: AsRef<[u8]> + Debug {
+NFSPROC3_NULL,
+NFSPROC3_LOOKUP(String, u32),
+NFSPROC3_CHECK(fhandle<T>),
+}
+#[derive(Debug, PartialEq)]
+pub enum NFS_V3Reply<T> where T: AsRef<[u8]> + Debug {
+NFSPROC3_NULL,
+NFSPROC3_LOOKUP(fhandle<T>),
+NFSPROC3_CHECK(bool),
+}
+"#
+    );
+
     test_convert!(
         test_typedef_generic_array_variable_constant_opaque,
         r#"
@@ -777,4 +1295,129 @@ pub struct alias<T: AsRef<[u8]> + Debug>(pub T);
 pub struct alias<T: AsRef<[u8]> + Debug>(pub T);
 "#
     );
+
+    test_convert!(
+        test_serde,
+        r#"
+            typedef opaque fhandle<>;
+            enum kind {
+                A = 1
+            };
+            struct thing {
+                unsigned int type;
+                opaque data<>;
+                string name<>;
+            };
+            union result switch (int status) {
+                case 0:
+                    fhandle fh;
+                case 1:
+                    kind k;
+                default:
+                    void;
+            };
+		"#,
+        r#"#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(bound(serialize = "T: AsRef<[u8]>", deserialize = "T: From<Vec<u8>>"))]
+pub struct fhandle<T: AsRef<[u8]> + Debug>(#[serde(with = "opaque_serde")] pub T);
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[repr(i32)]
+pub enum kind {
+A = 1,
+}
+impl kind {
+pub const fn value(&self) -> i32 {
+match self {
+Self::A => Self::A as i32,
+}
+}
+}
+impl From<kind> for i32 {
+fn from(v: kind) -> i32 {
+v.value()
+}
+}
+impl TryFrom<i32> for kind {
+type Error = Error;
+
+fn try_from(v: i32) -> Result<Self, Self::Error> {
+Ok(match v {
+1 => Self::A,
+d => return Err(Error::UnknownVariant(d)),
+})
+}
+}
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(bound(serialize = "T: AsRef<[u8]>", deserialize = "T: From<Vec<u8>>"))]
+pub enum result<T> where T: AsRef<[u8]> + Debug {
+#[serde(rename = "0")]
+v_0(fhandle<T>),
+#[serde(rename = "1")]
+v_1(kind),
+default(i32),
+}
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(bound(serialize = "T: AsRef<[u8]>", deserialize = "T: From<Vec<u8>>"))]
+pub struct thing<T> where T: AsRef<[u8]> + Debug {
+#[serde(rename = "type")]
+pub type_v: u32,
+#[serde(with = "opaque_serde")]
+pub data: T,
+pub name: String,
+}
+"#,
+        false,
+        Attributes::new("#[derive(Debug, PartialEq)]").with_serde(OpaqueFormat::Base64)
+    );
+
+    #[test]
+    fn test_borrowed() {
+        let ast = Ast::parse(
+            r#"
+			typedef opaque fhandle<>;
+			struct point {
+				int x;
+			};
+			struct entry {
+				string name<>;
+				fhandle fh;
+				point *origin;
+			};
+			union result switch (int status) {
+				case 0:
+					entry e;
+				default:
+					void;
+			};
+		"#,
+            &Default::default(),
+            true,
+        )
+        .unwrap();
+
+        let mut got = String::new();
+        print_types(&mut got, &ast, &Attributes::new("#[derive(Debug)]"), false).unwrap();
+
+        assert_eq!(
+            got,
+            r#"#[derive(Debug)]
+pub struct entry<'a> {
+pub name: &'a str,
+pub fh: fhandle<'a>,
+pub origin: Option<Box<point>>,
+}
+#[derive(Debug)]
+pub struct fhandle<'a>(pub &'a [u8]);
+#[derive(Debug)]
+pub struct point {
+pub x: i32,
+}
+#[derive(Debug)]
+pub enum result<'a> {
+v_0(entry<'a>),
+default(i32),
+}
+"#
+        );
+    }
 }
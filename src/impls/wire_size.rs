@@ -1,5 +1,5 @@
-use super::{NonDigitName, SafeName};
-use crate::ast::{indexes::AstType, Ast, BasicType};
+use super::{is_mapped, NonDigitName, SafeName};
+use crate::ast::{indexes::AstType, ArrayType, Ast, BasicType};
 use crate::impls::template::*;
 use crate::Result;
 
@@ -8,19 +8,26 @@ pub fn print_impl_wire_size<W: std::fmt::Write, T: FromTemplate>(
     template: T,
     ast: &Ast,
 ) -> Result<()> {
-    for item in ast.types().iter() {
+    for item in ast.emitted_types() {
         match item {
             AstType::Struct(v) => {
                 print_impl(&mut w, template, v.name(), ast, |w| {
                     for f in v.fields.iter() {
-                        writeln!(w, r#"self.{}.wire_size() +"#, SafeName(&f.field_name))?;
-
-                        // In-line opaques require padding
+                        writeln!(
+                            w,
+                            r#"self.{}.{}() +"#,
+                            SafeName(&f.field_name),
+                            wire_size_fn(ast, f.field_value.unwrap_array())
+                        )?;
+
+                        // In-line opaques require padding, and a length
+                        // prefix unless they are fixed size.
                         if f.contains_opaque() {
                             writeln!(
                                 w,
-                                r#" pad_length(self.{}.wire_size()) +"#,
-                                SafeName(&f.field_name)
+                                r#" pad_length(self.{}.wire_size()) +{}"#,
+                                SafeName(&f.field_name),
+                                length_prefix(&f.field_value, " 4 +")
                             )?;
                         }
                     }
@@ -44,15 +51,25 @@ pub fn print_impl_wire_size<W: std::fmt::Write, T: FromTemplate>(
                         // 		// statement
                         //
                         for c_value in case.case_values.iter() {
-                            write!(
-                                w,
-                                r#"Self::{}(inner) => inner.wire_size()"#,
-                                NonDigitName(SafeName(c_value))
-                            )?;
+                            let f = wire_size_fn(ast, case.field_value.unwrap_array());
+                            match c_value.as_str() {
+                                "default" => write!(w, "Self::default(_, inner) => inner.{}()", f)?,
+                                c => write!(
+                                    w,
+                                    r#"Self::{}(inner) => inner.{}()"#,
+                                    NonDigitName(SafeName(c)),
+                                    f
+                                )?,
+                            }
 
-                            // In-line opaques require padding
+                            // In-line opaques require padding, and a length
+                            // prefix unless they are fixed size.
                             if case.contains_opaque() {
-                                writeln!(w, r#" + pad_length(inner.wire_size()),"#)?;
+                                writeln!(
+                                    w,
+                                    r#" + pad_length(inner.wire_size()){},"#,
+                                    length_prefix(&case.field_value, " + 4")
+                                )?;
                             } else {
                                 writeln!(w, ",")?;
                             }
@@ -61,11 +78,10 @@ pub fn print_impl_wire_size<W: std::fmt::Write, T: FromTemplate>(
 
                     // There may also be several "void" cases
                     for c in v.void_cases.iter() {
-                        writeln!(w, "Self::{} => 0,", NonDigitName(SafeName(c.as_str())))?;
-                    }
-
-                    if v.default.is_some() {
-                        writeln!(w, "Self::default => 0,")?;
+                        match c.as_str() {
+                            "default" => writeln!(w, "Self::default(_) => 0,")?,
+                            c => writeln!(w, "Self::{} => 0,", NonDigitName(SafeName(c)))?,
+                        }
                     }
 
                     writeln!(w, "}}")?;
@@ -80,6 +96,13 @@ pub fn print_impl_wire_size<W: std::fmt::Write, T: FromTemplate>(
                 })?;
             }
 
+            // Mapped typedefs are replaced by the external type.
+            AstType::Typedef(v)
+                if ast
+                    .mappings()
+                    .get(v.alias.unwrap_array().as_str())
+                    .is_some() => {}
+
             AstType::Typedef(v) => {
                 print_impl(
                     &mut w,
@@ -87,12 +110,16 @@ pub fn print_impl_wire_size<W: std::fmt::Write, T: FromTemplate>(
                     v.alias.unwrap_array().as_str(),
                     ast,
                     |w| {
-                        writeln!(w, "self.0.wire_size()")?;
+                        writeln!(w, "self.0.{}()", wire_size_fn(ast, &v.target))?;
 
                         // If the target is opaque, it needs padding, and a
-                        // length prefix adding.
+                        // length prefix adding unless it is fixed size.
                         if let BasicType::Opaque = v.target {
-                            writeln!(w, "+ pad_length(self.0.wire_size()) + 4")?;
+                            writeln!(
+                                w,
+                                "+ pad_length(self.0.wire_size()){}",
+                                length_prefix(&v.alias, " + 4")
+                            )?;
                         }
 
                         Ok(())
@@ -105,6 +132,24 @@ pub fn print_impl_wire_size<W: std::fmt::Write, T: FromTemplate>(
     Ok(())
 }
 
+/// Returns the name of the method returning the wire size of a value of type
+/// `t`.
+fn wire_size_fn(ast: &Ast, t: &BasicType) -> &'static str {
+    match is_mapped(ast, t) {
+        true => "wire_size_mapped",
+        false => "wire_size",
+    }
+}
+
+/// Returns `prefix` if an opaque of type `t` is encoded with a length prefix,
+/// or an empty string if it is fixed size.
+fn length_prefix<'a>(t: &ArrayType<BasicType>, prefix: &'a str) -> &'a str {
+    match t {
+        ArrayType::FixedSize(_, _) => "",
+        _ => prefix,
+    }
+}
+
 fn print_impl<W: std::fmt::Write, T: FromTemplate, F: Fn(&mut W) -> Result<()>>(
     mut w: W,
     template: T,
@@ -113,7 +158,13 @@ fn print_impl<W: std::fmt::Write, T: FromTemplate, F: Fn(&mut W) -> Result<()>>(
     func: F,
 ) -> Result<()> {
     if ast.generics().contains(name) {
-        writeln!(w, "impl WireSize for {}<{}> {{", name, template.type_name(),)?;
+        writeln!(
+            w,
+            "impl{} WireSize for {}<{}> {{",
+            template.impl_generics(),
+            name,
+            template.type_name(),
+        )?;
     } else {
         writeln!(w, r#"impl WireSize for {} {{"#, name)?;
     }
@@ -261,7 +312,27 @@ self.f.wire_size() +
 self.g.wire_size() +
 self.h.wire_size() +
 self.i.wire_size() +
- pad_length(self.i.wire_size()) +
+ pad_length(self.i.wire_size()) + 4 +
+0
+}
+}
+"#
+    );
+
+    test_convert!(
+        test_struct_opaque_arrays_generic,
+        r#"
+			struct small {
+				opaque a[8];
+				opaque b<8>;
+			};
+		"#,
+        r#"impl WireSize for small<Bytes> {
+fn wire_size(&self) -> usize {
+self.a.wire_size() +
+ pad_length(self.a.wire_size()) +
+self.b.wire_size() +
+ pad_length(self.b.wire_size()) + 4 +
 0
 }
 }
@@ -354,7 +425,7 @@ Self::type(inner) => inner.wire_size(),
 fn wire_size(&self) -> usize {
 4 + match self {
 Self::v_1(inner) => inner.wire_size(),
-Self::v_2(inner) => inner.wire_size() + pad_length(inner.wire_size()),
+Self::v_2(inner) => inner.wire_size() + pad_length(inner.wire_size()) + 4,
 }
 }
 }
@@ -421,7 +492,7 @@ Self::v_3 => 0,
 fn wire_size(&self) -> usize {
 4 + match self {
 Self::v_1(inner) => inner.wire_size(),
-Self::default => 0,
+Self::default(_) => 0,
 }
 }
 }
@@ -493,6 +564,20 @@ self.0.wire_size()
 + pad_length(self.0.wire_size()) + 4
 }
 }
+"#
+    );
+
+    test_convert!(
+        test_typedef_fixed_array_generic,
+        r#"
+			typedef opaque  alias[NFS4_FHSIZE];
+		"#,
+        r#"impl WireSize for alias<Bytes> {
+fn wire_size(&self) -> usize {
+self.0.wire_size()
++ pad_length(self.0.wire_size())
+}
+}
 "#
     );
 }
@@ -4,14 +4,18 @@
 //! ```toml
 //! [build-dependencies]
 //! # For the code generation
-//! fastxdr = "1.0"
+//! fastxdr = "2.0"
 //!
 //! [dependencies]
-//! # Required dependencies of the generated code
-//! thiserror = "1.0"
-//! bytes = "0.5"
+//! # The runtime used by the generated code
+//! fastxdr = "2.0"
 //! ```
 //!
+//! The generated code imports its error type, encoding traits and helpers from
+//! [`runtime`] rather than defining its own copy, so types generated from
+//! different specs share them. Crates only using the generated code can disable
+//! the default features to skip building the generator.
+//!
 //! And add a `build.rs` at the crate root (not in `src`!):
 //!
 //! ```no_run
@@ -21,11 +25,12 @@
 //!     println!("cargo:rerun-if-changed=xdr_spec.x");
 //!
 //!     // Read from xdr_spec.x, writing the generated code to out.rs
+//! #   #[cfg(feature = "generator")]
 //!     std::fs::write(
 //!         std::path::Path::new(std::env::var("OUT_DIR").unwrap().as_str()).join("out.rs"),
 //!         fastxdr::Generator::default()
 //!             .generate(include_str!("xdr_spec.x"))
-//!             .unwrap(),
+//!             .unwrap_or_else(|e| panic!("{}", e)),
 //!     )
 //!     .unwrap();
 //! }
@@ -44,11 +49,35 @@
 //! code directly for reading.
 
 #![allow(clippy::needless_doctest_main)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+pub mod runtime;
+
+#[cfg(feature = "generator")]
 pub mod ast;
+#[cfg(feature = "generator")]
 pub mod impls;
 
-use crate::impls::{print_impl_from, print_impl_wire_size, print_types, template};
+#[cfg(feature = "generator")]
+mod error;
+#[cfg(feature = "generator")]
+pub use error::{Error, Span};
+
+#[cfg(feature = "generator")]
+pub use crate::impls::{OpaqueFormat, TypeKind, TypeMatch};
+
+#[cfg(feature = "generator")]
+use crate::ast::{Ast, Source};
+#[cfg(feature = "generator")]
+use crate::impls::{
+    print_clients, print_impl_encode, print_impl_from, print_impl_mappings, print_impl_procedures,
+    print_impl_wire_size, print_services, print_types, template, Attributes,
+};
+#[cfg(feature = "generator")]
+use std::collections::BTreeMap;
+#[cfg(feature = "generator")]
 use std::fmt::Write;
 
 /// `DEFAULT_DERIVE` defines the default "derive" line prepended to type
@@ -56,57 +85,1028 @@ use std::fmt::Write;
 ///
 /// Custom "derive" lines can be used when generating Rust types with
 /// [`Generator::with_derive()`](Generator::with_derive).
+#[cfg(feature = "generator")]
 pub const DEFAULT_DERIVE: &str = "#[derive(Debug, PartialEq)]";
 
-pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + 'static>>;
+#[cfg(feature = "generator")]
+pub type Result<T> = std::result::Result<T, Error>;
 
 /// Re-export of `Bytes` used for generated code.
 pub use bytes;
 /// Re-export of `thiserror` used for generated code.
+#[cfg(feature = "std")]
 pub use thiserror;
 
+/// The ONC RPC message envelope types from RFC 5531, included in the generated
+/// code by [`Generator::with_rpc_envelope()`].
+#[cfg(feature = "generator")]
+pub const RPC_ENVELOPE_SPEC: &str = include_str!("rpc.x");
+
+/// The lints allowed in the generated module, as the generated names follow
+/// the XDR spec rather than Rust conventions.
+#[cfg(feature = "generator")]
+const ALLOWED_LINTS: &str =
+    "#![allow(non_camel_case_types, dead_code, unused_mut, unused_variables, unreachable_patterns)]";
+
+/// The types the generated code allocates, imported from `std` or `alloc`
+/// depending on the target environment.
+#[cfg(feature = "generator")]
+const ALLOC_IMPORTS: &str = "boxed::Box, format, string::{String, ToString}, vec, vec::Vec";
+
+#[cfg(feature = "generator")]
 #[derive(Debug)]
 pub struct Generator {
-    derive: String,
+    attributes: Attributes,
+    tokio_codec: bool,
+    rpc_envelope: bool,
+    rpc_client: bool,
+    rpc_server: bool,
+    bounded_types: bool,
+    borrowed: bool,
+    no_std: bool,
+    type_mappings: BTreeMap<String, String>,
+    module: Option<String>,
+    module_visibility: String,
+    module_doc: String,
+    module_attributes: Vec<String>,
+    module_per_file: bool,
 }
 
+#[cfg(feature = "generator")]
 impl std::default::Default for Generator {
     fn default() -> Self {
         Generator {
-            derive: DEFAULT_DERIVE.to_string(),
+            attributes: Attributes::new(DEFAULT_DERIVE),
+            tokio_codec: false,
+            rpc_envelope: false,
+            rpc_client: false,
+            rpc_server: false,
+            bounded_types: false,
+            borrowed: false,
+            no_std: false,
+            type_mappings: BTreeMap::new(),
+            module: Some("xdr".to_string()),
+            module_visibility: "pub".to_string(),
+            module_doc: "Types generated from an XDR specification.".to_string(),
+            module_attributes: Vec::new(),
+            module_per_file: false,
         }
     }
 }
 
+#[cfg(feature = "generator")]
 impl Generator {
     pub fn with_derive<D: AsRef<str>>(self, derive: D) -> Self {
         Self {
-            derive: derive.as_ref().to_string(),
+            attributes: self.attributes.with_derive(derive),
+            ..self
+        }
+    }
+
+    /// Derives the comma separated `traits`, such as `"Clone, Eq, Hash"`, for
+    /// the types selected by `m` in addition to the derive line.
+    ///
+    /// Types are selected by a name glob pattern such as `"nfs_*"`, or by a
+    /// [`TypeKind`]. Traits a type cannot derive are omitted for that type -
+    /// `Eq`, `Hash` and `Ord` for types containing an `f32` or `f64`, `Copy`
+    /// for types containing heap allocated values, and `Default` for unions,
+    /// enums and the types containing them. Traits are also omitted for the
+    /// types containing a generated type that does not derive them.
+    pub fn with_type_derive<M: Into<TypeMatch>, D: AsRef<str>>(self, m: M, traits: D) -> Self {
+        Self {
+            attributes: self.attributes.with_type_derive(m, traits),
+            ..self
+        }
+    }
+
+    /// Prepends `attribute`, such as `#[non_exhaustive]`, to the types selected
+    /// by `m`.
+    pub fn with_type_attribute<M: Into<TypeMatch>, A: AsRef<str>>(
+        self,
+        m: M,
+        attribute: A,
+    ) -> Self {
+        Self {
+            attributes: self.attributes.with_type_attribute(m, attribute),
+            ..self
+        }
+    }
+
+    /// Prepends `attribute` to the struct fields and enum variants matching
+    /// the `type.field` glob `path`, such as `"nfs_*.fh"`.
+    ///
+    /// Paths use the names in the XDR spec, and match the variants generated
+    /// for union cases and enum values as well as struct fields.
+    pub fn with_field_attribute<P: AsRef<str>, A: AsRef<str>>(self, path: P, attribute: A) -> Self {
+        Self {
+            attributes: self.attributes.with_field_attribute(path, attribute),
+            ..self
+        }
+    }
+
+    /// Generates a `codec` module containing `XdrCodec<T>`, a
+    /// [`tokio_util::codec`] `Decoder` and `Encoder` for record-marked streams
+    /// of XDR messages.
+    ///
+    /// The generated code requires the `tokio-util` crate (with the `codec`
+    /// feature) as a dependency of the crate including it.
+    ///
+    /// [`tokio_util::codec`]: https://docs.rs/tokio-util/latest/tokio_util/codec/
+    pub fn with_tokio_codec(self) -> Self {
+        Self {
+            tokio_codec: true,
+            ..self
+        }
+    }
+
+    /// Generates the ONC RPC message envelope types (`rpc_msg`, `call_body`,
+    /// `reply_body`, `opaque_auth` and friends) from [`RPC_ENVELOPE_SPEC`]
+    /// alongside the types in the spec.
+    ///
+    /// The `AUTH_NONE`, `AUTH_SYS` and `AUTH_SHORT` bodies of an
+    /// `opaque_auth` can be decoded into the generated `Auth` enum with
+    /// `opaque_auth::decode_body()`.
+    ///
+    /// The spec passed to [`generate()`](Generator::generate) must not define
+    /// any of the envelope types or their enum variants.
+    pub fn with_rpc_envelope(self) -> Self {
+        Self {
+            rpc_envelope: true,
+            ..self
+        }
+    }
+
+    /// Generates an async client for each program version in the spec, with
+    /// a method per procedure.
+    ///
+    /// For a program version named `NFS_V3`, an `NFS_V3Client<R>` is
+    /// generated that sends calls over any transport implementing the
+    /// generated `client::Transport` trait.
+    ///
+    /// This implies [`with_rpc_envelope()`](Generator::with_rpc_envelope).
+    pub fn with_rpc_client(self) -> Self {
+        Self {
+            rpc_client: true,
+            rpc_envelope: true,
+            ..self
+        }
+    }
+
+    /// Generates a service trait for each program version in the spec, with a
+    /// method per procedure, and a server dispatching calls to it.
+    ///
+    /// For a program version named `NFS_V3`, an `NFS_V3Service` trait is
+    /// generated, and an `NFS_V3Server<S>` wrapping any implementation of it.
+    /// Received call messages are passed to the generated `server::handle()`,
+    /// which returns the encoded reply, including `PROC_UNAVAIL` and
+    /// `GARBAGE_ARGS` replies for calls that cannot be dispatched.
+    ///
+    /// This implies [`with_rpc_envelope()`](Generator::with_rpc_envelope).
+    pub fn with_rpc_server(self) -> Self {
+        Self {
+            rpc_server: true,
+            rpc_envelope: true,
+            ..self
+        }
+    }
+
+    /// Generates length-checked newtypes for bounded strings (`string<N>`),
+    /// bounded opaques (`opaque<N>`) and fixed size opaques (`opaque[N]`) in
+    /// place of `String` and the generic opaque `T`.
+    ///
+    /// The generated `BoundedString<N>`, `BoundedOpaque<T, N>` and
+    /// `FixedOpaque<T, N>` types expose their bound as an associated `MAX` (or
+    /// `LEN`) constant, and can only be constructed from values within the
+    /// bound, so values that would violate the spec cannot be encoded.
+    pub fn with_bounded_types(self) -> Self {
+        Self {
+            bounded_types: true,
+            ..self
+        }
+    }
+
+    /// Generates types borrowing from the `&'a [u8]` buffer they are decoded
+    /// from, for zero-copy decoding of read-only buffers such as an mmap or a
+    /// packet capture.
+    ///
+    /// Opaque data is a `&'a [u8]` slice of the buffer in place of the generic
+    /// `T`, and strings are a `&'a str`, so types containing either have a
+    /// lifetime parameter `'a`. All types implement `TryFrom<&'a [u8]>` and
+    /// `TryFrom<&mut &'a [u8]>`, which advances the slice past the decoded
+    /// value, in place of the `Bytes` implementations.
+    ///
+    /// The RPC envelope, client, server and tokio codec are built on `Bytes`,
+    /// and cannot be generated with borrowed types, and neither can bounded
+    /// types, serde implementations or mappings of types that borrow from the
+    /// buffer.
+    pub fn with_borrowed_decoding(self) -> Self {
+        Self {
+            borrowed: true,
+            ..self
+        }
+    }
+
+    /// Generates code depending only on `core` and `alloc`, for use in
+    /// `#![no_std]` crates such as embedded firmware.
+    ///
+    /// The crate including the generated code must declare `extern crate
+    /// alloc;` and provide a global allocator, and must depend on `fastxdr`
+    /// with `default-features = false` (and `resolver = "2"`, so the build
+    /// dependency on the generator does not enable `std`). The runtime
+    /// [`Error`](runtime::Error) implements `core::error::Error`, which
+    /// requires Rust 1.81 or later.
+    ///
+    /// The RPC client, server and tokio codec require `std` and cannot be
+    /// generated in this mode.
+    pub fn with_no_std(self) -> Self {
+        Self {
+            no_std: true,
+            ..self
+        }
+    }
+
+    /// Derives `serde::Serialize` and `serde::Deserialize` for every generated
+    /// struct, union, enum and typedef, so decoded values can be exported as
+    /// JSON or any other serde format.
+    ///
+    /// Opaque data is serialised as a `format` encoded string, and unions as
+    /// externally tagged objects keyed by the case name, with the `default`
+    /// case holding the discriminant and value. Fields and cases keep their
+    /// XDR names.
+    ///
+    /// A [`Quadruple`](runtime::Quadruple) is always serialised as a hex
+    /// string, as the runtime type is shared by all generated modules.
+    ///
+    /// The crate including the generated code must depend on `serde` with the
+    /// `derive` feature, and enable the `serde` feature of `fastxdr`, which
+    /// provides the opaque encodings. Types replaced by
+    /// [`with_type_mapping()`](Generator::with_type_mapping) must implement
+    /// the serde traits themselves, and optional opaque fields and fixed size
+    /// arrays of more than 32 elements are not supported.
+    pub fn with_serde(self, format: OpaqueFormat) -> Self {
+        Self {
+            attributes: self.attributes.with_serde(format),
+            ..self
+        }
+    }
+
+    /// Replaces the XDR type `name` with the existing Rust type at `path`,
+    /// such as `uuid::Uuid` for a 16 byte `opaque` typedef.
+    ///
+    /// References to the XDR type use the Rust type, which is converted to and
+    /// from the wire representation of the XDR type by a user implementation
+    /// of the generated `XdrMapping` trait. The wire representation of a
+    /// mapped struct, union or enum is the generated type itself, while a
+    /// mapped typedef is not generated, and its wire representation is the
+    /// target type (`Bytes` for an `opaque`).
+    ///
+    /// The generated `EncodeMapped` trait is implemented for `path` in place
+    /// of the runtime `Encode` and `WireSize` traits, which cannot be
+    /// implemented for types defined in other crates. Each Rust type can
+    /// replace a single XDR type.
+    pub fn with_type_mapping<N: AsRef<str>, P: AsRef<str>>(mut self, name: N, path: P) -> Self {
+        self.type_mappings
+            .insert(name.as_ref().to_string(), path.as_ref().to_string());
+        self
+    }
+
+    /// Sets the name of the generated module, `xdr` by default.
+    pub fn with_module_name<N: AsRef<str>>(self, name: N) -> Self {
+        Self {
+            module: Some(name.as_ref().to_string()),
+            ..self
+        }
+    }
+
+    /// Sets the visibility of the generated module, such as `pub(crate)`, or
+    /// an empty string for a private module. The module is `pub` by default.
+    pub fn with_module_visibility<V: AsRef<str>>(self, visibility: V) -> Self {
+        Self {
+            module_visibility: visibility.as_ref().to_string(),
+            ..self
+        }
+    }
+
+    /// Sets the doc comment of the generated module, which may span multiple
+    /// lines.
+    pub fn with_module_doc<D: AsRef<str>>(self, doc: D) -> Self {
+        Self {
+            module_doc: doc.as_ref().to_string(),
+            ..self
+        }
+    }
+
+    /// Adds an inner attribute, such as `#![allow(missing_docs)]`, to the
+    /// generated module.
+    pub fn with_module_attribute<A: AsRef<str>>(mut self, attribute: A) -> Self {
+        self.module_attributes.push(attribute.as_ref().to_string());
+        self
+    }
+
+    /// Generates the types of each file passed to
+    /// [`generate_files()`](Generator::generate_files) in a module of their
+    /// own, named after the file without its extension, such as `nfs4_base`
+    /// for `specs/nfs4_base.x`.
+    ///
+    /// The file modules are nested in the generated module, which holds the
+    /// shared runtime and any RPC envelope types, and import the types of the
+    /// other files they reference.
+    pub fn with_module_per_file(self) -> Self {
+        Self {
+            module_per_file: true,
+            ..self
+        }
+    }
+
+    /// Generates the types and their implementations without a wrapping
+    /// module, for inclusion in a module of the caller's choosing.
+    ///
+    /// The module doc comment and attributes are not generated. The including
+    /// module should allow the `non_camel_case_types`, `dead_code`,
+    /// `unused_mut`, `unused_variables` and `unreachable_patterns` lints, as
+    /// the generated names follow the XDR spec:
+    ///
+    /// ```compile_fail
+    /// mod nfs {
+    ///     #![allow(non_camel_case_types, dead_code, unused_mut, unused_variables, unreachable_patterns)]
+    ///     include!(concat!(env!("OUT_DIR"), "/out.rs"));
+    /// }
+    /// ```
+    pub fn without_module(self) -> Self {
+        Self {
+            module: None,
+            ..self
         }
     }
 
     pub fn generate<T: AsRef<str>>(&self, xdr: T) -> Result<String> {
+        self.generate_sources(vec![Source::new(None, xdr.as_ref())])
+    }
+
+    /// Generates the code for several spec files, given as (file name, XDR)
+    /// pairs, in which each spec can reference the types and constants
+    /// defined by the others.
+    ///
+    /// A definition repeated in more than one file is rejected, and errors
+    /// name the file they occur in. All the types are generated in a single
+    /// module, unless [`with_module_per_file()`](Generator::with_module_per_file)
+    /// is set.
+    pub fn generate_files<N: AsRef<str>, S: AsRef<str>>(&self, files: &[(N, S)]) -> Result<String> {
+        let sources = files
+            .iter()
+            .map(|(name, xdr)| Source::new(Some(name.as_ref()), xdr.as_ref()))
+            .collect();
+
+        self.generate_sources(sources)
+    }
+
+    fn generate_sources(&self, mut sources: Vec<Source>) -> Result<String> {
+        // Parse the envelope types after the user's specs, so the indexes of
+        // the user's sources are unchanged.
+        if self.rpc_envelope {
+            sources.push(Source::new(None, RPC_ENVELOPE_SPEC));
+        }
+
         // Create the AST
-        let ast = crate::ast::Ast::new(xdr.as_ref())?;
+        let mut ast = Ast::parse_sources(sources, &self.type_mappings, self.borrowed)?;
+        if self.borrowed {
+            self.check_borrowed(&ast)?;
+        }
+        if self.no_std {
+            self.check_no_std()?;
+        }
+
+        // Reject specs that would generate code that fails to compile,
+        // reporting the first problem found.
+        if let Some(e) = ast.validate().into_iter().next() {
+            return Err(e);
+        }
 
         let mut out = String::new();
 
-        // Print the file header
-        writeln!(out, "{}", include_str!("header.rs"))?;
+        // Open the module, and import the runtime and the allocating types of
+        // the std or no_std environment
+        if let Some(ref name) = self.module {
+            self.print_module_open(&mut out, name)?;
+        }
+        let alloc = match self.no_std {
+            true => "alloc",
+            false => "std",
+        };
+        writeln!(out, "    #[allow(unused_imports)]")?;
+        writeln!(out, "    use {}::{{{}}};", alloc, ALLOC_IMPORTS)?;
+        writeln!(out, "{}", include_str!("prelude.rs"))?;
 
-        // Generate the types
-        print_types(&mut out, &ast, &self.derive.as_str())?;
+        // Write the mapping traits for any types replaced by external types
+        if !self.type_mappings.is_empty() {
+            writeln!(out, "{}", include_str!("mapped.rs"))?;
+        }
 
-        // Write the two from traits, one for Bytes and one for &mut Bytes
-        print_impl_from(&mut out, template::bytes::Bytes, &ast)?;
-        print_impl_from(&mut out, template::bytes::RefMutBytes, &ast)?;
+        // Optionally write the length-checked types for bounded values
+        if self.bounded_types {
+            writeln!(out, "{}", include_str!("bounded.rs"))?;
+        }
+
+        // Optionally import the runtime serde helper for opaque data, and
+        // write the serde implementations of the bounded types
+        if let Some(format) = self.attributes.serde() {
+            writeln!(
+                out,
+                "    #[allow(unused_imports)]\n    use fastxdr::runtime::{} as opaque_serde;",
+                format.module()
+            )?;
+            if self.bounded_types {
+                writeln!(out, "{}", include_str!("bounded_serde.rs"))?;
+            }
+        }
+
+        // Generate the types and their implementations, either all together,
+        // or the types of each spec file in a module of their own, leaving the
+        // unnamed sources (such as the envelope types) in the outer module.
+        if self.module_per_file {
+            let modules = module_names(&ast)?;
+            for (file, module) in modules.iter().enumerate() {
+                if module.is_none() {
+                    ast.set_emitted(Some(file));
+                    self.print_definitions(&mut out, &ast)?;
+                }
+            }
 
-        // Write the wire_size() implementations
-        print_impl_wire_size(&mut out, template::bytes::Bytes, &ast)?;
+            for (file, module) in modules.iter().enumerate() {
+                let module = match module {
+                    Some(v) => v,
+                    None => continue,
+                };
 
-        // End the header.rs with a closing }
-        writeln!(out, "}}")?;
+                writeln!(out, "pub mod {} {{", module)?;
+                writeln!(
+                    out,
+                    "//! Types generated from `{}`.\n",
+                    ast.files().nth(file).flatten().unwrap_or_default()
+                )?;
+                writeln!(out, "use super::*;")?;
+                for dep in ast.dependencies(file) {
+                    if let Some(ref name) = modules[dep] {
+                        writeln!(out, "use super::{}::*;", name)?;
+                    }
+                }
+
+                ast.set_emitted(Some(file));
+                self.print_definitions(&mut out, &ast)?;
+                writeln!(out, "}}")?;
+            }
+            ast.set_emitted(None);
+        } else {
+            self.print_definitions(&mut out, &ast)?;
+        }
+
+        // Optionally write the RPC client runtime
+        if self.rpc_client {
+            writeln!(out, "{}", include_str!("client.rs"))?;
+        }
+
+        // Optionally write the RPC server runtime
+        if self.rpc_server {
+            writeln!(out, "{}", include_str!("server.rs"))?;
+        }
+
+        // Optionally write the helpers for the RPC envelope types
+        if self.rpc_envelope {
+            writeln!(out, "{}", include_str!("rpc.rs"))?;
+        }
+
+        // Optionally write the tokio codec
+        if self.tokio_codec {
+            writeln!(out, "{}", include_str!("codec.rs"))?;
+        }
+
+        // Close the module opened before the header
+        if self.module.is_some() {
+            writeln!(out, "}}")?;
+        }
 
         Ok(out)
     }
+
+    /// Writes the types emitted by `ast`, and their implementations.
+    fn print_definitions(&self, out: &mut String, ast: &Ast) -> Result<()> {
+        // Generate the types
+        print_types(&mut *out, ast, &self.attributes, self.bounded_types)?;
+
+        // Write the two from traits, one for the buffer and one for a mutable
+        // reference to it, and the wire_size() implementations
+        if self.borrowed {
+            print_impl_from(&mut *out, template::slice::Slice, ast, false)?;
+            print_impl_from(&mut *out, template::slice::RefMutSlice, ast, false)?;
+            print_impl_wire_size(&mut *out, template::slice::Slice, ast)?;
+        } else {
+            print_impl_from(&mut *out, template::bytes::Bytes, ast, self.bounded_types)?;
+            print_impl_from(
+                &mut *out,
+                template::bytes::RefMutBytes,
+                ast,
+                self.bounded_types,
+            )?;
+            print_impl_wire_size(&mut *out, template::bytes::Bytes, ast)?;
+        }
+
+        // Write the Encode implementations for serialising into a BufMut
+        print_impl_encode(&mut *out, ast)?;
+
+        // Write the Encode and WireSize implementations for mapped types
+        print_impl_mappings(&mut *out, ast)?;
+
+        // Write the procedure dispatch for any RPC program definitions
+        if self.borrowed {
+            print_impl_procedures(&mut *out, template::slice::Slice, ast)?;
+        } else {
+            print_impl_procedures(&mut *out, template::bytes::Bytes, ast)?;
+        }
+
+        // Optionally write the RPC clients and service traits
+        if self.rpc_client {
+            print_clients(&mut *out, template::bytes::Bytes, ast)?;
+        }
+        if self.rpc_server {
+            print_services(&mut *out, template::bytes::Bytes, ast)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error naming the first option that cannot be generated with
+    /// types borrowing from the buffer.
+    fn check_borrowed(&self, ast: &crate::ast::Ast) -> Result<()> {
+        let conflicts = [
+            (self.rpc_client, "the RPC client"),
+            (self.rpc_server, "the RPC server"),
+            (self.rpc_envelope, "the RPC envelope"),
+            (self.tokio_codec, "the tokio codec"),
+            (self.bounded_types, "bounded types"),
+            (self.attributes.serde().is_some(), "serde"),
+        ];
+        if let Some((_, name)) = conflicts.iter().find(|(enabled, _)| *enabled) {
+            return Err(format!("borrowed decoding cannot be combined with {}", name).into());
+        }
+
+        // A mapped type has no lifetime to name the buffer its wire
+        // representation borrows from.
+        if let Some(name) = self
+            .type_mappings
+            .keys()
+            .find(|name| ast.generics().contains(name))
+        {
+            return Err(format!(
+                "borrowed decoding cannot be combined with a mapping of {}, as it borrows from the buffer",
+                name
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error naming the first option that requires `std`.
+    fn check_no_std(&self) -> Result<()> {
+        let conflicts = [
+            (self.rpc_client, "the RPC client"),
+            (self.rpc_server, "the RPC server"),
+            (self.tokio_codec, "the tokio codec"),
+        ];
+        if let Some((_, name)) = conflicts.iter().find(|(enabled, _)| *enabled) {
+            return Err(format!("no_std code cannot be combined with {}", name).into());
+        }
+
+        Ok(())
+    }
+
+    fn print_module_open(&self, out: &mut String, name: &str) -> Result<()> {
+        match self.module_visibility.trim() {
+            "" => writeln!(out, "mod {} {{", name)?,
+            v => writeln!(out, "{} mod {} {{", v, name)?,
+        }
+
+        for line in self.module_doc.lines() {
+            match line.trim_end() {
+                "" => writeln!(out, "    //!")?,
+                line => writeln!(out, "    //! {}", line)?,
+            }
+        }
+        writeln!(
+            out,
+            "    //!\n    //! Do NOT modify the generated file directly.\n"
+        )?;
+
+        writeln!(out, "    {}", ALLOWED_LINTS)?;
+        for attribute in self.module_attributes.iter() {
+            writeln!(out, "    {}", attribute)?;
+        }
+        writeln!(out)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the name of the module generated for each source of `ast` by
+/// [`Generator::with_module_per_file()`], or `None` for the unnamed sources.
+#[cfg(feature = "generator")]
+fn module_names(ast: &Ast) -> Result<Vec<Option<String>>> {
+    let mut names: Vec<Option<String>> = Vec::new();
+    for (file, path) in ast.files().enumerate() {
+        let path = match path {
+            Some(v) => v,
+            None => {
+                names.push(None);
+                continue;
+            }
+        };
+
+        let stem = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        let stem = stem.rsplit_once('.').map(|(v, _)| v).unwrap_or(stem);
+        let mut name = stem
+            .chars()
+            .map(|c| match c {
+                c if c.is_ascii_alphanumeric() => c.to_ascii_lowercase(),
+                _ => '_',
+            })
+            .collect::<String>();
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            name.insert(0, '_');
+        }
+
+        if let Some(other) = names[..file].iter().position(|v| v.as_ref() == Some(&name)) {
+            return Err(format!(
+                "files {} and {} generate the same module {}",
+                ast.files().nth(other).flatten().unwrap_or_default(),
+                path,
+                name
+            )
+            .into());
+        }
+        if ast.types().get(&name).is_some() || ast.programs().get(&name).is_some() {
+            return Err(format!(
+                "the module {} generated for file {} has the same name as a type",
+                name, path
+            )
+            .into());
+        }
+
+        names.push(Some(name));
+    }
+
+    Ok(names)
+}
+
+#[cfg(all(test, feature = "generator"))]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = "struct thing { uint32_t a; };";
+
+    #[test]
+    fn test_tokio_codec_disabled() {
+        let got = Generator::default().generate(SPEC).unwrap();
+        assert!(!got.contains("pub mod codec"));
+    }
+
+    #[test]
+    fn test_tokio_codec_enabled() {
+        let got = Generator::default()
+            .with_derive("#[derive(Debug)]")
+            .with_tokio_codec()
+            .generate(SPEC)
+            .unwrap();
+
+        assert!(got.contains("pub mod codec"));
+        assert!(got.contains("#[derive(Debug)]\npub struct thing"));
+    }
+
+    #[test]
+    fn test_rpc_envelope_spec_valid() {
+        let ast = crate::ast::Ast::new(RPC_ENVELOPE_SPEC).unwrap();
+        assert_eq!(ast.validate(), vec![]);
+    }
+
+    #[test]
+    fn test_rpc_envelope() {
+        let got = Generator::default().generate(SPEC).unwrap();
+        assert!(!got.contains("pub struct rpc_msg"));
+
+        let got = Generator::default()
+            .with_rpc_envelope()
+            .generate(SPEC)
+            .unwrap();
+        assert!(got.contains("pub struct rpc_msg"));
+        assert!(got.contains("pub enum Auth"));
+    }
+
+    #[test]
+    fn test_rpc_envelope_conflict() {
+        let got = Generator::default()
+            .with_rpc_envelope()
+            .generate("struct rpc_msg { uint32_t xid; };");
+
+        assert!(matches!(got, Err(Error::DuplicateDefinition { .. })));
+    }
+
+    #[test]
+    fn test_rpc_client() {
+        let spec = "program P { version V1 { void NULL(void) = 0; } = 1; } = 1;";
+
+        let got = Generator::default().generate(spec).unwrap();
+        assert!(!got.contains("pub mod client"));
+
+        let got = Generator::default()
+            .with_rpc_client()
+            .generate(spec)
+            .unwrap();
+        assert!(got.contains("pub mod client"));
+        assert!(got.contains("pub struct V1Client<R>"));
+        assert!(got.contains("pub struct rpc_msg"));
+    }
+
+    #[test]
+    fn test_rpc_server() {
+        let spec = "program P { version V1 { void NULL(void) = 0; } = 1; } = 1;";
+
+        let got = Generator::default().generate(spec).unwrap();
+        assert!(!got.contains("pub mod server"));
+
+        let got = Generator::default()
+            .with_rpc_server()
+            .generate(spec)
+            .unwrap();
+        assert!(got.contains("pub mod server"));
+        assert!(got.contains("pub trait V1Service"));
+        assert!(got.contains("pub struct rpc_msg"));
+    }
+
+    #[test]
+    fn test_module_options() {
+        let got = Generator::default().generate(SPEC).unwrap();
+        assert!(got.starts_with("pub mod xdr {\n    //! Types generated from an XDR"));
+
+        let got = Generator::default()
+            .with_module_name("nfs")
+            .with_module_visibility("pub(crate)")
+            .with_module_doc("NFSv3 wire types.\n\nSee RFC 1813.")
+            .with_module_attribute("#![allow(missing_docs)]")
+            .generate(SPEC)
+            .unwrap();
+
+        assert!(got.starts_with(
+            "pub(crate) mod nfs {
+    //! NFSv3 wire types.
+    //!
+    //! See RFC 1813.
+    //!
+    //! Do NOT modify the generated file directly.
+
+    #![allow(non_camel_case_types, dead_code, unused_mut, unused_variables, unreachable_patterns)]
+    #![allow(missing_docs)]
+"
+        ));
+        assert!(got.ends_with("}\n}\n"));
+
+        let got = Generator::default()
+            .with_module_visibility("")
+            .generate(SPEC)
+            .unwrap();
+        assert!(got.starts_with("mod xdr {\n"));
+    }
+
+    #[test]
+    fn test_without_module() {
+        let got = Generator::default()
+            .without_module()
+            .with_module_attribute("#![allow(missing_docs)]")
+            .generate(SPEC)
+            .unwrap();
+
+        assert!(!got.contains("mod xdr"));
+        assert!(!got.contains("#![allow"));
+        assert!(got.contains("\npub struct thing {"));
+
+        // The braces are balanced without the closing brace of the module.
+        assert_eq!(got.matches('{').count(), got.matches('}').count());
+    }
+
+    #[test]
+    fn test_bounded_types() {
+        let spec = "struct thing { string a<4>; };";
+
+        let got = Generator::default().generate(spec).unwrap();
+        assert!(!got.contains("pub struct BoundedString"));
+        assert!(got.contains("pub a: String,"));
+
+        let got = Generator::default()
+            .with_bounded_types()
+            .generate(spec)
+            .unwrap();
+        assert!(got.contains("pub struct BoundedString"));
+        assert!(got.contains("pub a: BoundedString<4>,"));
+    }
+
+    #[test]
+    fn test_type_mapping() {
+        let spec = "typedef opaque uuid4[16]; struct thing { uuid4 id; opaque data<>; };";
+
+        let got = Generator::default()
+            .with_type_mapping("uuid4", "uuid::Uuid")
+            .generate(spec)
+            .unwrap();
+        assert!(!got.contains("pub struct uuid4"));
+        assert!(got.contains("pub id: uuid::Uuid,"));
+        assert!(got.contains("pub trait XdrMapping: Sized {"));
+        assert!(got.contains("impl EncodeMapped for uuid::Uuid {"));
+        assert!(!got.contains("impl Encode for uuid::Uuid {"));
+
+        let got = Generator::default()
+            .with_type_mapping("missing", "uuid::Uuid")
+            .generate(spec)
+            .unwrap_err();
+        assert_eq!(got.to_string(), "type mapping for undefined type missing");
+    }
+
+    #[test]
+    fn test_serde() {
+        let spec = "struct thing { string a<4>; opaque data<>; };";
+
+        let got = Generator::default().generate(spec).unwrap();
+        assert!(!got.contains("serde"));
+
+        let got = Generator::default()
+            .with_serde(OpaqueFormat::Hex)
+            .generate(spec)
+            .unwrap();
+        assert!(got.contains(
+            "    #[allow(unused_imports)]\n    use fastxdr::runtime::opaque_hex as opaque_serde;"
+        ));
+        assert!(!got.contains("for BoundedString<N>"));
+        assert!(got.contains(
+            "#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]\n#[serde(bound("
+        ));
+
+        let got = Generator::default()
+            .with_serde(OpaqueFormat::Base64)
+            .with_bounded_types()
+            .generate(spec)
+            .unwrap();
+        assert!(got.contains("use fastxdr::runtime::opaque_base64 as opaque_serde;"));
+        assert!(got.contains("impl<const N: usize> serde::Serialize for BoundedString<N> {"));
+    }
+
+    #[test]
+    fn test_borrowed_decoding() {
+        let spec = "typedef opaque fhandle<>; struct thing { string a<>; fhandle fh; };";
+
+        let got = Generator::default()
+            .with_borrowed_decoding()
+            .generate(spec)
+            .unwrap();
+        assert!(got.contains("pub struct thing<'a> {"));
+        assert!(got.contains("impl<'a> TryFrom<&'a [u8]> for thing<'a> {"));
+        assert!(!got.contains("for thing<Bytes>"));
+
+        let got = Generator::default()
+            .with_borrowed_decoding()
+            .with_rpc_client()
+            .generate(spec)
+            .unwrap_err();
+        assert_eq!(
+            got.to_string(),
+            "borrowed decoding cannot be combined with the RPC client"
+        );
+
+        let got = Generator::default()
+            .with_borrowed_decoding()
+            .with_type_mapping("fhandle", "crate::Handle")
+            .generate(spec)
+            .unwrap_err();
+        assert_eq!(
+            got.to_string(),
+            "borrowed decoding cannot be combined with a mapping of fhandle, as it borrows from the buffer"
+        );
+    }
+
+    #[test]
+    fn test_no_std() {
+        let spec = "struct thing { string a<>; opaque data<>; };";
+
+        let got = Generator::default().generate(spec).unwrap();
+        assert!(got.contains("use std::{boxed::Box, "));
+        assert!(!got.contains("alloc::"));
+
+        let got = Generator::default().with_no_std().generate(spec).unwrap();
+        assert!(got.contains("use alloc::{boxed::Box, "));
+        assert!(!got.contains("std::"));
+
+        let got = Generator::default()
+            .with_no_std()
+            .with_rpc_server()
+            .generate(spec)
+            .unwrap_err();
+        assert_eq!(
+            got.to_string(),
+            "no_std code cannot be combined with the RPC server"
+        );
+    }
+
+    #[test]
+    fn test_runtime() {
+        let spec = "struct thing { string a<>; opaque data<>; };";
+
+        // The runtime is imported rather than generated, so the types of all
+        // generated specs share it.
+        let got = Generator::default().generate(spec).unwrap();
+        assert!(got.contains("pub use fastxdr::runtime::{"));
+        assert!(!got.contains("pub enum Error"));
+        assert!(!got.contains("pub trait WireSize"));
+        assert!(!got.contains("fn pad_length"));
+        assert!(!got.contains("trait XdrMapping"));
+    }
+
+    const FILES: [(&str, &str); 3] = [
+        (
+            "specs/base.x",
+            "const MAX = 8; enum status { OK = 0, ERR = 1 }; typedef opaque handle<MAX>;",
+        ),
+        (
+            "specs/ops.x",
+            "union res switch (status s) { case OK: handle h; case ERR: void; };",
+        ),
+        ("specs/nfs-extra.x", "struct extra { uint32_t a; };"),
+    ];
+
+    #[test]
+    fn test_generate_files() {
+        let got = Generator::default().generate_files(&FILES).unwrap();
+        assert!(got.contains("pub const MAX: u32 = 8;"));
+        assert!(got.contains("pub enum res<T> where T: AsRef<[u8]> + Debug {"));
+        assert!(got.contains("pub struct extra {"));
+        assert!(!got.contains("pub mod base"));
+    }
+
+    #[test]
+    fn test_module_per_file() {
+        let got = Generator::default()
+            .with_module_per_file()
+            .generate_files(&FILES)
+            .unwrap();
+
+        let base = got.find("pub mod base {").unwrap();
+        let ops = got.find("pub mod ops {").unwrap();
+        let extra = got.find("pub mod nfs_extra {").unwrap();
+        assert!(base < ops && ops < extra);
+
+        // Each module holds the types of its file, importing the shared
+        // runtime and the modules defining the types it references.
+        assert!(got[base..ops].contains("//! Types generated from `specs/base.x`."));
+        assert!(got[base..ops].contains("use super::*;\npub const MAX: u32 = 8;"));
+        assert!(got[ops..extra].contains("use super::*;\nuse super::base::*;\n"));
+        assert!(got[ops..extra].contains("pub enum res<T>"));
+        assert!(!got[extra..].contains("use super::base::*;"));
+        assert!(got[extra..].contains("pub struct extra {"));
+    }
+
+    #[test]
+    fn test_module_per_file_envelope() {
+        let got = Generator::default()
+            .with_module_per_file()
+            .with_rpc_envelope()
+            .generate_files(&FILES)
+            .unwrap();
+
+        // The envelope types are generated in the outer module, shared by all
+        // the files.
+        let base = got.find("pub mod base {").unwrap();
+        assert!(got[..base].contains("pub struct rpc_msg<T>"));
+        assert!(!got[base..].contains("pub struct rpc_msg<T>"));
+    }
+
+    #[test]
+    fn test_module_per_file_conflicts() {
+        let files = [
+            ("a/spec.x", "struct a { int x; };"),
+            ("b/spec.x", "struct b { int x; };"),
+        ];
+        let err = Generator::default()
+            .with_module_per_file()
+            .generate_files(&files)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files a/spec.x and b/spec.x generate the same module spec"
+        );
+
+        let files = [("thing.x", "struct thing { int x; };")];
+        let err = Generator::default()
+            .with_module_per_file()
+            .generate_files(&files)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the module thing generated for file thing.x has the same name as a type"
+        );
+    }
 }
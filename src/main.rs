@@ -1,17 +1,37 @@
 use fastxdr::Generator;
-use fastxdr::Result;
 use std::env;
 
-fn main() -> Result<()> {
-    if env::args().len() < 2 {
-        println!("usage: {} ./path/to/spec.x", env::args().next().unwrap());
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut generator = Generator::default();
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--module-per-file" => generator = generator.with_module_per_file(),
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.is_empty() {
+        println!(
+            "usage: {} [--module-per-file] ./path/to/spec.x [./path/to/other.x ...]",
+            env::args().next().unwrap()
+        );
         std::process::exit(1);
     }
 
-    for e in env::args().skip(1) {
-        let xdr = std::fs::read_to_string(e)?;
-        let code = Generator::default().generate(&xdr)?;
-        println!("{}", code);
+    // Parse the specs together, so each can reference the types defined in
+    // the others.
+    let files = paths
+        .into_iter()
+        .map(|path| std::fs::read_to_string(&path).map(|xdr| (path, xdr)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match generator.generate_files(&files) {
+        Ok(code) => println!("{}", code),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
     }
 
     Ok(())
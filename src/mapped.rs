@@ -0,0 +1,89 @@
+    /// `XdrMapping` converts an external Rust type to and from the XDR type it
+    /// replaces in the generated code.
+    ///
+    /// The generated code decodes and encodes the wire representation of the
+    /// XDR type, converting it with this trait.
+    pub trait XdrMapping: Sized {
+        /// The wire representation - the generated type for a mapped struct,
+        /// union or enum, or the target type of a mapped typedef.
+        type Wire;
+
+        /// Converts a decoded wire value, returning an error if it is not a
+        /// valid value of the mapped type.
+        fn from_xdr(wire: Self::Wire) -> Result<Self, Error>;
+
+        /// Converts the value into its wire representation for encoding.
+        fn to_xdr(&self) -> Self::Wire;
+    }
+
+    /// `EncodeMapped` encodes the mapped types through their wire
+    /// representation.
+    ///
+    /// The runtime `Encode` and `WireSize` traits cannot be implemented for
+    /// types defined in other crates, so values of mapped types are encoded
+    /// with this trait instead.
+    pub trait EncodeMapped {
+        /// Encodes the wire representation of the value into `buf`.
+        fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error>;
+
+        /// Returns the encoded size of the wire representation in bytes.
+        fn wire_size_mapped(&self) -> usize;
+    }
+
+    impl<T: EncodeMapped> EncodeMapped for Option<T> {
+        fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+            match self {
+                Some(inner) => {
+                    buf.put_u32(1);
+                    inner.encode_mapped(buf)
+                }
+                None => {
+                    buf.put_u32(0);
+                    Ok(())
+                }
+            }
+        }
+
+        fn wire_size_mapped(&self) -> usize {
+            4 + match self {
+                Some(inner) => inner.wire_size_mapped(),
+                None => 0,
+            }
+        }
+    }
+
+    impl<T: EncodeMapped> EncodeMapped for Box<T> {
+        fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+            (**self).encode_mapped(buf)
+        }
+
+        fn wire_size_mapped(&self) -> usize {
+            (**self).wire_size_mapped()
+        }
+    }
+
+    impl<T: EncodeMapped, const N: usize> EncodeMapped for [T; N] {
+        fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+            // Fixed size arrays have no length prefix.
+            for t in self.iter() {
+                t.encode_mapped(buf)?;
+            }
+            Ok(())
+        }
+
+        fn wire_size_mapped(&self) -> usize {
+            let x = self.iter().map(|v| v.wire_size_mapped()).sum::<usize>();
+            x + pad_length(x)
+        }
+    }
+
+    impl<T: EncodeMapped> EncodeMapped for Vec<T> {
+        fn encode_mapped<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+            buf.write_variable_array_with(self, None, |buf, t| t.encode_mapped(buf))
+        }
+
+        fn wire_size_mapped(&self) -> usize {
+            let x = self.iter().map(|v| v.wire_size_mapped()).sum::<usize>();
+            4 + x + pad_length(x)
+        }
+    }
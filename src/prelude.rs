@@ -0,0 +1,12 @@
+    // The runtime shared by all generated code. Not every spec uses all of
+    // the imports.
+    #[allow(unused_imports)]
+    pub use fastxdr::runtime::{
+        record, DeserialiserExt, Encode, Error, Quadruple, SerialiserExt, WireSize,
+    };
+    #[allow(unused_imports)]
+    use fastxdr::runtime::pad_length;
+    #[allow(unused_imports)]
+    use fastxdr::bytes::{Buf, BufMut, Bytes, BytesMut};
+    #[allow(unused_imports)]
+    use core::{convert::TryFrom, fmt::Debug};
@@ -0,0 +1,46 @@
+    /// The version of the RPC protocol implemented by the generated clients
+    /// and servers.
+    pub const RPC_VERSION: u32 = 2;
+
+    /// The decoded body of an [`opaque_auth`] credential or verifier.
+    #[derive(Debug, PartialEq)]
+    pub enum Auth {
+        None,
+        Sys(authsys_parms),
+        Short(Bytes),
+
+        /// The raw body of a flavour without typed decoding, such as
+        /// `AUTH_DH` or `RPCSEC_GSS`.
+        Other(Bytes),
+    }
+
+    impl opaque_auth<Bytes> {
+        /// Returns an `AUTH_NONE` credential or verifier with an empty body.
+        pub fn none() -> Self {
+            Self {
+                flavor: auth_flavor::AUTH_NONE,
+                body: Bytes::new(),
+            }
+        }
+
+        /// Returns an `AUTH_SYS` credential containing `params`.
+        pub fn sys(params: &authsys_parms) -> Result<Self, Error> {
+            let mut body = BytesMut::new();
+            params.encode(&mut body)?;
+
+            Ok(Self {
+                flavor: auth_flavor::AUTH_SYS,
+                body: body.freeze(),
+            })
+        }
+
+        /// Decodes the body according to its authentication flavour.
+        pub fn decode_body(&self) -> Result<Auth, Error> {
+            Ok(match self.flavor {
+                auth_flavor::AUTH_NONE => Auth::None,
+                auth_flavor::AUTH_SYS => Auth::Sys(authsys_parms::try_from(self.body.clone())?),
+                auth_flavor::AUTH_SHORT => Auth::Short(self.body.clone()),
+                _ => Auth::Other(self.body.clone()),
+            })
+        }
+    }
@@ -0,0 +1,976 @@
+//! The runtime shared by all generated code: the [`Error`] type, the
+//! [`DeserialiserExt`] and [`SerialiserExt`] buffer extensions, and the
+//! [`Encode`] and [`WireSize`] traits with their implementations for the XDR
+//! primitive types.
+//!
+//! Generated modules re-export the public items of the runtime, so the types
+//! generated from several specs share a single `Error` type and the same
+//! traits. The generated code must be built against the version of this crate
+//! that generated it.
+//!
+//! The runtime depends only on `core` and `alloc`, and is available without
+//! the code generator by disabling the default features of this crate.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::mem::size_of;
+
+use alloc::boxed::Box;
+use alloc::string::{FromUtf8Error, String};
+use alloc::vec::Vec;
+use bytes::{Buf, BufMut, Bytes};
+
+pub mod record;
+
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "serde")]
+pub use self::serde::{opaque_base64, opaque_hex};
+
+/// The errors returned when decoding or encoding XDR values.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    InvalidLength,
+
+    /// The buffer ended before the value was fully decoded, and at least
+    /// `needed` more bytes are required to make progress.
+    ///
+    /// Unlike [`Error::InvalidLength`], retrying the decode once more data
+    /// has been received may succeed.
+    Incomplete {
+        needed: usize,
+    },
+
+    NonUtf8String(FromUtf8Error),
+
+    NonUtf8Str(core::str::Utf8Error),
+
+    InvalidBoolean,
+
+    UnknownVariant(i32),
+
+    UnknownOptionVariant(u32),
+
+    UnknownProcedure(u32),
+
+    Unknown(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "invalid message length"),
+            Self::Incomplete { needed } => {
+                write!(f, "incomplete message, {} more bytes needed", needed)
+            }
+            Self::NonUtf8String(e) => write!(f, "non-utf8 characters in string: {}", e),
+            Self::NonUtf8Str(e) => write!(f, "non-utf8 characters in string: {}", e),
+            Self::InvalidBoolean => write!(f, "invalid boolean value"),
+            Self::UnknownVariant(v) => write!(f, "unknown enum variant {}", v),
+            Self::UnknownOptionVariant(v) => write!(f, "unknown option variant {}", v),
+            Self::UnknownProcedure(v) => write!(f, "unknown procedure {}", v),
+            Self::Unknown(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(v: FromUtf8Error) -> Self {
+        Self::NonUtf8String(v)
+    }
+}
+
+impl From<core::str::Utf8Error> for Error {
+    fn from(v: core::str::Utf8Error) -> Self {
+        Self::NonUtf8Str(v)
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::NonUtf8String(e) => Some(e),
+            Self::NonUtf8Str(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An XDR quadruple-precision float, holding its 16 byte big-endian IEEE
+/// 754 binary128 encoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Quadruple(pub [u8; 16]);
+
+/// `DeserialiserExt` reads XDR values from a buffer, advancing past each
+/// value read.
+pub trait DeserialiserExt: Buf + Sized {
+    /// The type of opaque data read from the buffer.
+    type Sliced: WireSize;
+    /// The type of strings read from the buffer.
+    type Str;
+    type TryFrom;
+
+    fn read_bytes(&mut self, n: usize) -> Result<Self::Sliced, Error>;
+    fn read_variable_array<T>(&mut self, max: Option<usize>) -> Result<Vec<T>, Error>
+    where
+        T: TryFrom<Self::TryFrom, Error = Error> + WireSize;
+
+    /// Reads a variable length UTF8-compatible string from the buffer.
+    fn read_string(&mut self, max: Option<usize>) -> Result<Self::Str, Error>;
+
+    // Try and read a u32 if self contains enough data.
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        ensure_remaining(self, size_of::<u32>())?;
+        Ok(self.get_u32())
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Error> {
+        ensure_remaining(self, size_of::<u64>())?;
+        Ok(self.get_u64())
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Error> {
+        ensure_remaining(self, size_of::<i32>())?;
+        Ok(self.get_i32())
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Error> {
+        ensure_remaining(self, size_of::<i64>())?;
+        Ok(self.get_i64())
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Error> {
+        ensure_remaining(self, size_of::<f32>())?;
+        Ok(self.get_f32())
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Error> {
+        ensure_remaining(self, size_of::<f64>())?;
+        Ok(self.get_f64())
+    }
+
+    fn read_quadruple(&mut self) -> Result<Quadruple, Error> {
+        ensure_remaining(self, size_of::<Quadruple>())?;
+        let mut v = [0; 16];
+        self.copy_to_slice(&mut v);
+        Ok(Quadruple(v))
+    }
+
+    fn read_bool(&mut self) -> Result<bool, Error> {
+        ensure_remaining(self, size_of::<i32>())?;
+        match self.get_i32() {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::InvalidBoolean),
+        }
+    }
+
+    /// Try to read an opaque XDR array, prefixed by a length u32 and padded
+    /// modulo 4.
+    fn read_variable_bytes(&mut self, max: Option<usize>) -> Result<Self::Sliced, Error> {
+        let n = self.read_u32()? as usize;
+
+        if let Some(limit) = max {
+            if n > limit {
+                return Err(Error::InvalidLength);
+            }
+        }
+
+        self.read_bytes(n)
+    }
+
+    /// Read a variable length array, prefixed by the element count,
+    /// decoding each element with `f`.
+    fn read_variable_array_with<T, F>(
+        &mut self,
+        max: Option<usize>,
+        mut f: F,
+    ) -> Result<Vec<T>, Error>
+    where
+        F: FnMut(&mut Self) -> Result<T, Error>,
+    {
+        let n = self.read_u32()? as usize;
+
+        if let Some(limit) = max {
+            if n > limit {
+                return Err(Error::InvalidLength);
+            }
+        }
+
+        (0..n).map(|_| f(self)).collect()
+    }
+}
+
+impl DeserialiserExt for Bytes {
+    type Sliced = Self;
+    type Str = String;
+    type TryFrom = Bytes;
+
+    /// Try to read an opaque XDR array with a fixed length and padded modulo 4.
+    fn read_bytes(&mut self, n: usize) -> Result<Self::Sliced, Error> {
+        // Validate the buffer contains enough data, including any padding.
+        ensure_remaining(self, n + pad_length(n))?;
+
+        let data = self.slice(..n);
+
+        // Advance the buffer cursor, including any padding.
+        self.advance(n + pad_length(n));
+
+        Ok(data)
+    }
+
+    fn read_variable_array<T>(&mut self, max: Option<usize>) -> Result<Vec<T>, Error>
+    where
+        T: TryFrom<Self, Error = Error> + WireSize,
+    {
+        let n = self.read_u32()? as usize;
+
+        if let Some(limit) = max {
+            if n > limit {
+                return Err(Error::InvalidLength);
+            }
+        }
+
+        // Try and decode n instances of T.
+        let mut sum = 0;
+        let mut out = Vec::with_capacity(n);
+        for _ in 0..n {
+            let t = T::try_from(self.clone())?;
+            ensure_remaining(self, t.wire_size())?;
+            self.advance(t.wire_size());
+            sum += t.wire_size();
+            out.push(t);
+        }
+
+        ensure_remaining(self, pad_length(sum))?;
+        self.advance(pad_length(sum));
+
+        Ok(out)
+    }
+
+    fn read_string(&mut self, max: Option<usize>) -> Result<String, Error> {
+        let b = self
+            .read_variable_bytes(max)?
+            .into_iter()
+            .collect::<Vec<u8>>();
+        String::from_utf8(b).map_err(|e| e.into())
+    }
+}
+
+impl<'a> DeserialiserExt for &'a [u8] {
+    type Sliced = Self;
+    type Str = &'a str;
+    type TryFrom = &'a [u8];
+
+    /// Try to read an opaque XDR array with a fixed length and padded modulo 4.
+    fn read_bytes(&mut self, n: usize) -> Result<Self::Sliced, Error> {
+        // Validate the buffer contains enough data, including any padding.
+        ensure_remaining(self, n + pad_length(n))?;
+
+        let data = &self[..n];
+
+        // Advance the buffer cursor, including any padding.
+        self.advance(n + pad_length(n));
+
+        Ok(data)
+    }
+
+    fn read_variable_array<T>(&mut self, max: Option<usize>) -> Result<Vec<T>, Error>
+    where
+        T: TryFrom<Self, Error = Error> + WireSize,
+    {
+        let n = self.read_u32()? as usize;
+
+        if let Some(limit) = max {
+            if n > limit {
+                return Err(Error::InvalidLength);
+            }
+        }
+
+        // Try and decode n instances of T.
+        let mut sum = 0;
+        let mut out = Vec::with_capacity(n);
+        for _ in 0..n {
+            let t = T::try_from(*self)?;
+            ensure_remaining(self, t.wire_size())?;
+            self.advance(t.wire_size());
+            sum += t.wire_size();
+            out.push(t);
+        }
+
+        ensure_remaining(self, pad_length(sum))?;
+        self.advance(pad_length(sum));
+
+        Ok(out)
+    }
+
+    fn read_string(&mut self, max: Option<usize>) -> Result<&'a str, Error> {
+        let b = self.read_variable_bytes(max)?;
+        core::str::from_utf8(b).map_err(|e| e.into())
+    }
+}
+
+/// `Encode` is implemented by types that can be serialised into their XDR
+/// wire representation.
+pub trait Encode {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error>;
+}
+
+pub trait SerialiserExt: BufMut + Sized {
+    /// Write an opaque XDR array with a fixed length of `n`, padded modulo 4.
+    fn write_bytes(&mut self, v: &[u8], n: usize) -> Result<(), Error> {
+        if v.len() != n {
+            return Err(Error::InvalidLength);
+        }
+
+        self.put_slice(v);
+        self.put_bytes(0, pad_length(n));
+
+        Ok(())
+    }
+
+    /// Write an opaque XDR array, prefixed by a length u32 and padded
+    /// modulo 4.
+    fn write_variable_bytes(&mut self, v: &[u8], max: Option<usize>) -> Result<(), Error> {
+        if let Some(limit) = max {
+            if v.len() > limit {
+                return Err(Error::InvalidLength);
+            }
+        }
+
+        let n = u32::try_from(v.len()).map_err(|_| Error::InvalidLength)?;
+        self.put_u32(n);
+        self.write_bytes(v, v.len())
+    }
+
+    /// Writes a variable length UTF8 string to the buffer.
+    fn write_string(&mut self, v: &str, max: Option<usize>) -> Result<(), Error> {
+        self.write_variable_bytes(v.as_bytes(), max)
+    }
+
+    /// Write a variable length array of `T`, prefixed by the element count.
+    fn write_variable_array<T>(&mut self, v: &[T], max: Option<usize>) -> Result<(), Error>
+    where
+        T: Encode,
+    {
+        self.write_variable_array_with(v, max, |buf, t| t.encode(buf))
+    }
+
+    /// Write a variable length array, prefixed by the element count,
+    /// encoding each element with `f`.
+    fn write_variable_array_with<T, F>(
+        &mut self,
+        v: &[T],
+        max: Option<usize>,
+        mut f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&mut Self, &T) -> Result<(), Error>,
+    {
+        if let Some(limit) = max {
+            if v.len() > limit {
+                return Err(Error::InvalidLength);
+            }
+        }
+
+        let n = u32::try_from(v.len()).map_err(|_| Error::InvalidLength)?;
+        self.put_u32(n);
+        for t in v.iter() {
+            f(self, t)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<B: BufMut> SerialiserExt for B {}
+
+macro_rules! encode_primitive {
+    ($($type:ty => $put:ident),+) => {
+        $(
+            impl Encode for $type {
+                fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+                    buf.$put(*self);
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
+
+encode_primitive!(u32 => put_u32, i32 => put_i32, f32 => put_f32);
+encode_primitive!(u64 => put_u64, i64 => put_i64, f64 => put_f64);
+
+impl Encode for Quadruple {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+        buf.put_slice(&self.0);
+        Ok(())
+    }
+}
+
+impl Encode for bool {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+        buf.put_u32(*self as u32);
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Encode for [T; N]
+where
+    T: Encode,
+{
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+        // Fixed size arrays have no length prefix.
+        for t in self.iter() {
+            t.encode(buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Encode for Option<T>
+where
+    T: Encode,
+{
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+        match self {
+            Some(inner) => {
+                buf.put_u32(1);
+                inner.encode(buf)
+            }
+            None => {
+                buf.put_u32(0);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<T> Encode for Box<T>
+where
+    T: Encode,
+{
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+        use core::ops::Deref;
+        self.deref().encode(buf)
+    }
+}
+
+pub trait WireSize {
+    fn wire_size(&self) -> usize;
+}
+
+macro_rules! wiresize_fixed {
+    ($size:literal, $($type:ty),+) => {
+        $(
+            impl WireSize for $type {
+                fn wire_size(&self) -> usize {
+                    $size
+                }
+            }
+        )+
+    };
+}
+
+wiresize_fixed!(1, u8);
+wiresize_fixed!(4, u32, i32, f32, bool);
+wiresize_fixed!(8, u64, i64, f64);
+wiresize_fixed!(16, Quadruple);
+
+impl WireSize for Bytes {
+    fn wire_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> WireSize for Vec<T>
+where
+    T: WireSize,
+{
+    fn wire_size(&self) -> usize {
+        // Element count prefix of 4 bytes, plus the individual element lengths
+        // (which may vary between elements).
+        let x = self.iter().map(|v| v.wire_size()).sum::<usize>();
+        4 + x + pad_length(x)
+    }
+}
+
+impl<T> WireSize for [T]
+where
+    T: WireSize,
+{
+    fn wire_size(&self) -> usize {
+        // Individual element lengths (which may vary between elements) without
+        // a length byte as [T] is for fixed size arrays.
+        let x = self.iter().map(|v| v.wire_size()).sum::<usize>();
+        x + pad_length(x)
+    }
+}
+
+impl<T> WireSize for Option<T>
+where
+    T: WireSize,
+{
+    fn wire_size(&self) -> usize {
+        4 + match self {
+            Some(inner) => inner.wire_size(),
+            None => 0,
+        }
+    }
+}
+
+impl<T> WireSize for Box<T>
+where
+    T: WireSize,
+{
+    fn wire_size(&self) -> usize {
+        use core::ops::Deref;
+        self.deref().wire_size()
+    }
+}
+
+impl WireSize for String {
+    fn wire_size(&self) -> usize {
+        4 + self.len() + pad_length(self.len())
+    }
+}
+
+impl WireSize for &[u8] {
+    fn wire_size(&self) -> usize {
+        // Padded as for [T], which method calls on a &[u8] resolve to.
+        self.len() + pad_length(self.len())
+    }
+}
+
+impl WireSize for &str {
+    fn wire_size(&self) -> usize {
+        4 + self.len() + pad_length(self.len())
+    }
+}
+
+/// Returns [`Error::Incomplete`] if `buf` holds fewer than `n` bytes.
+#[inline]
+fn ensure_remaining<B: Buf>(buf: &B, n: usize) -> Result<(), Error> {
+    match n.saturating_sub(buf.remaining()) {
+        0 => Ok(()),
+        needed => Err(Error::Incomplete { needed }),
+    }
+}
+
+/// Return the amount of padding needed for a value of l bytes in length.
+#[inline]
+pub fn pad_length(l: usize) -> usize {
+    (4 - (l % 4)) % 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use bytes::BytesMut;
+
+    #[derive(Debug, PartialEq)]
+    struct TestStruct {
+        a: u32,
+    }
+
+    impl TryFrom<Bytes> for TestStruct {
+        type Error = Error;
+
+        fn try_from(mut v: Bytes) -> Result<Self, Self::Error> {
+            Ok(Self { a: v.read_u32()? })
+        }
+    }
+
+    impl WireSize for TestStruct {
+        fn wire_size(&self) -> usize {
+            self.a.wire_size()
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct VariableSizedStruct {
+        a: Vec<u32>,
+    }
+
+    impl TryFrom<Bytes> for VariableSizedStruct {
+        type Error = Error;
+
+        fn try_from(mut v: Bytes) -> Result<Self, Self::Error> {
+            // Stub, always has a len of 2
+            let x = v.read_u32()?;
+            if x != 2 {
+                panic!("expected len of 2, got {}", x);
+            }
+            Ok(Self {
+                a: vec![v.read_u32()?, v.read_u32()?],
+            })
+        }
+    }
+
+    impl WireSize for VariableSizedStruct {
+        fn wire_size(&self) -> usize {
+            self.a.wire_size()
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct UnalignedStruct {
+        a: u8,
+    }
+
+    wiresize_fixed!(1, UnalignedStruct);
+
+    impl TryFrom<Bytes> for UnalignedStruct {
+        type Error = Error;
+
+        fn try_from(mut v: Bytes) -> Result<Self, Self::Error> {
+            let s = v.slice(..1);
+            v.advance(1);
+            Ok(Self { a: s.as_ref()[0] })
+        }
+    }
+
+    #[test]
+    fn test_pad_length() {
+        assert_eq!(pad_length(0), 0);
+        assert_eq!(pad_length(1), 3);
+        assert_eq!(pad_length(2), 2);
+        assert_eq!(pad_length(3), 1);
+        assert_eq!(pad_length(4), 0);
+    }
+
+    #[test]
+    fn test_wire_size_basic_types() {
+        assert_eq!(42_u8.wire_size(), 1);
+        assert_eq!(42_u32.wire_size(), 4);
+        assert_eq!(42_i32.wire_size(), 4);
+        assert_eq!(42_u64.wire_size(), 8);
+        assert_eq!(42_i64.wire_size(), 8);
+        assert_eq!((42_f32).wire_size(), 4);
+        assert_eq!((42_f64).wire_size(), 8);
+
+        // Length prefix of 4 bytes, plus data 5 bytes, plus padding to mod 4
+        assert_eq!(String::from("test!").wire_size(), 4 + 5 + 3);
+
+        let b = Bytes::new();
+        assert_eq!(b.wire_size(), 0);
+
+        let b = BytesMut::new().freeze();
+        assert_eq!(b.wire_size(), 0);
+
+        let b = BytesMut::from("test!").freeze();
+        assert_eq!(b.wire_size(), 5);
+
+        let b: &[u8] = &[1, 2, 3, 4];
+        assert_eq!(b.wire_size(), 4);
+
+        let b: &[u8] = &[1, 2, 3, 4, 5];
+        assert_eq!(b.wire_size(), 8); // Padded
+    }
+
+    #[test]
+    fn test_wire_size_borrowed_opaque() {
+        // Generic code calls the &[u8] impl rather than the [T] impl.
+        fn wire_size<T: WireSize>(v: T) -> usize {
+            v.wire_size()
+        }
+
+        let b: &[u8] = &[1, 2, 3, 4, 5];
+        let mut buf = BytesMut::new();
+        buf.write_bytes(b, b.len()).unwrap();
+
+        assert_eq!(wire_size(b), buf.len());
+        assert_eq!(wire_size(b), b.wire_size());
+    }
+
+    #[test]
+    fn test_wire_size_vec() {
+        let v1: Vec<u32> = vec![1, 2, 3, 4];
+        assert_eq!(v1.wire_size(), 4 * 5);
+
+        let v2: Vec<u64> = vec![1, 2, 3, 4];
+        assert_eq!(v2.wire_size(), (8 * 4) + 4);
+    }
+
+    #[test]
+    fn test_wire_size_array() {
+        let v1: [u32; 4] = [1, 2, 3, 4];
+        assert_eq!(v1.wire_size(), 4 * 4);
+
+        let v2: [u64; 4] = [1, 2, 3, 4];
+        assert_eq!(v2.wire_size(), 8 * 4);
+    }
+
+    #[test]
+    fn test_variable_array_variable_len_struct() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(2); // 2 structs
+
+        buf.put_u32(2); // This struct has 2 values
+        buf.put_u32(1); // Struct 1
+        buf.put_u32(2); // Struct 2
+
+        buf.put_u32(2); // This struct has 2 values
+        buf.put_u32(3); // Struct 1
+        buf.put_u32(4); // Struct 2
+
+        buf.put_u32(123); // Remaining buffer
+        let mut buf = buf.freeze();
+
+        let got = buf
+            .read_variable_array::<VariableSizedStruct>(None)
+            .unwrap();
+
+        assert_eq!(got.len(), 2);
+        assert_eq!(
+            got.wire_size(),
+            4 + // Variable array length prefix
+
+            4 + // First struct array length prefix
+            8 + // First struct data
+
+            4 + // Second struct array length prefix
+            8 // Second struct data
+        );
+        assert_eq!(got[0], VariableSizedStruct { a: vec![1, 2] });
+
+        assert_eq!(buf.len(), 4);
+        assert_eq!(buf.as_ref(), &[0, 0, 0, 123]);
+    }
+
+    #[test]
+    fn test_variable_array_no_max() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(4); // Len=4
+        buf.put_u8(1); // Struct 1
+        buf.put_u8(2); // Struct 2
+        buf.put_u8(3); // Struct 3
+        buf.put_u8(4); // Struct 4
+        buf.put_u32(123); // Remaining buffer
+        let mut buf = buf.freeze();
+
+        let got = buf.read_variable_array::<UnalignedStruct>(None).unwrap();
+
+        assert_eq!(got.len(), 4);
+        assert_eq!(got.wire_size(), 4 + 4); // Inner vecs + vec length
+
+        assert_eq!(got[0], UnalignedStruct { a: 1 });
+        assert_eq!(got[0].wire_size(), 1);
+
+        assert_eq!(got[1], UnalignedStruct { a: 2 });
+        assert_eq!(got[1].wire_size(), 1);
+
+        assert_eq!(got[2], UnalignedStruct { a: 3 });
+        assert_eq!(got[2].wire_size(), 1);
+
+        assert_eq!(got[3], UnalignedStruct { a: 4 });
+        assert_eq!(got[3].wire_size(), 1);
+
+        assert_eq!(buf.len(), 4);
+        assert_eq!(buf.as_ref(), &[0, 0, 0, 123]);
+    }
+
+    #[test]
+    fn test_variable_array_no_max_with_padding() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(2); // Len=4
+        buf.put_u8(1); // Struct 1
+        buf.put_u8(2); // Struct 2
+        buf.put_u8(0); // Padding
+        buf.put_u8(0); // Padding
+        buf.put_u32(123); // Remaining buffer
+        let mut buf = buf.freeze();
+
+        let got = buf.read_variable_array::<UnalignedStruct>(None).unwrap();
+
+        assert_eq!(got.len(), 2);
+        assert_eq!(got.wire_size(), 4 + 4);
+        assert_eq!(got[0], UnalignedStruct { a: 1 });
+        assert_eq!(got[1], UnalignedStruct { a: 2 });
+
+        assert_eq!(buf.len(), 4);
+        assert_eq!(buf.as_ref(), &[0, 0, 0, 123]);
+    }
+
+    #[test]
+    fn test_read_variable_bytes_no_max() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(8); // Len=8
+        buf.put([1, 2, 3, 4, 5, 6, 7, 8].as_ref());
+        let mut buf = buf.freeze();
+
+        let got = buf.read_variable_bytes(None).unwrap();
+
+        assert_eq!(got.len(), 8);
+        assert_eq!(got.wire_size(), 8);
+        assert_eq!(got.as_ref(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        assert!(buf.is_empty());
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    #[test]
+    fn test_read_variable_bytes_no_max_with_padding() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(6); // Len=6 + 2 bytes padding
+        buf.put([1, 2, 3, 4, 5, 6, 0, 0].as_ref());
+        let mut buf = buf.freeze();
+
+        let got = buf.read_variable_bytes(None).unwrap();
+
+        assert_eq!(got.len(), 6);
+        assert_eq!(got.wire_size(), 6);
+        assert_eq!(got.as_ref(), &[1, 2, 3, 4, 5, 6]);
+
+        assert!(buf.is_empty());
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    #[test]
+    fn test_write_bytes_with_padding() {
+        let mut buf = BytesMut::new();
+        buf.write_bytes(&[1, 2, 3, 4, 5, 6], 6).unwrap();
+
+        assert_eq!(buf.as_ref(), &[1, 2, 3, 4, 5, 6, 0, 0]);
+    }
+
+    #[test]
+    fn test_write_bytes_wrong_length() {
+        let mut buf = BytesMut::new();
+        assert_eq!(buf.write_bytes(&[1, 2, 3], 4), Err(Error::InvalidLength));
+        assert_eq!(buf.len(), 0);
+    }
+
+    #[test]
+    fn test_write_variable_bytes_round_trip() {
+        let mut buf = BytesMut::new();
+        buf.write_variable_bytes(&[1, 2, 3, 4, 5, 6], Some(8))
+            .unwrap();
+        buf.put_u32(123); // Remaining buffer
+
+        assert_eq!(buf.len(), 4 + 6 + 2 + 4);
+
+        let mut buf = buf.freeze();
+        let got = buf.read_variable_bytes(Some(8)).unwrap();
+        assert_eq!(got.as_ref(), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(buf.as_ref(), &[0, 0, 0, 123]);
+    }
+
+    #[test]
+    fn test_write_variable_bytes_exceeds_max() {
+        let mut buf = BytesMut::new();
+        assert_eq!(
+            buf.write_variable_bytes(&[1, 2, 3], Some(2)),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_write_string_round_trip() {
+        let mut buf = BytesMut::new();
+        buf.write_string("test!", None).unwrap();
+
+        let mut buf = buf.freeze();
+        assert_eq!(buf.len(), String::from("test!").wire_size());
+        assert_eq!(buf.read_string(None).unwrap(), "test!");
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    #[test]
+    fn test_write_variable_array() {
+        let mut buf = BytesMut::new();
+        buf.write_variable_array(&[1_u32, 2, 3], None).unwrap();
+
+        assert_eq!(
+            buf.as_ref(),
+            &[0, 0, 0, 3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3]
+        );
+        assert_eq!(
+            buf.write_variable_array(&[1_u32, 2, 3], Some(2)),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_encode_option() {
+        let mut buf = BytesMut::new();
+        Some(Box::new(42_u32)).encode(&mut buf).unwrap();
+        None::<u32>.encode(&mut buf).unwrap();
+
+        assert_eq!(buf.as_ref(), &[0, 0, 0, 1, 0, 0, 0, 42, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_encode_bool() {
+        let mut buf = BytesMut::new();
+        true.encode(&mut buf).unwrap();
+        false.encode(&mut buf).unwrap();
+
+        let mut buf = buf.freeze();
+        assert_eq!(buf.read_bool(), Ok(true));
+        assert_eq!(buf.read_bool(), Ok(false));
+    }
+
+    #[test]
+    fn test_encode_quadruple() {
+        let v = Quadruple([7; 16]);
+        assert_eq!(v.wire_size(), 16);
+
+        let mut buf = BytesMut::new();
+        v.encode(&mut buf).unwrap();
+
+        let mut buf = buf.freeze();
+        assert_eq!(buf.read_quadruple(), Ok(v));
+        assert_eq!(buf.read_quadruple(), Err(Error::Incomplete { needed: 16 }));
+    }
+
+    #[test]
+    fn test_read_bool() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(0);
+        buf.put_u32(1);
+        buf.put_u32(2);
+        let mut buf = buf.freeze();
+
+        assert_eq!(buf.read_bool(), Ok(false));
+        assert_eq!(buf.read_bool(), Ok(true));
+        assert!(buf.read_bool().is_err());
+    }
+
+    #[test]
+    fn test_read_incomplete() {
+        let mut buf = Bytes::from_static(&[0, 0, 0]);
+        assert_eq!(buf.read_u32(), Err(Error::Incomplete { needed: 1 }));
+        assert_eq!(buf.read_u64(), Err(Error::Incomplete { needed: 5 }));
+        assert_eq!(buf.remaining(), 3);
+    }
+
+    #[test]
+    fn test_read_variable_bytes_incomplete() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(6); // Len=6 + 2 bytes padding
+        buf.put([1, 2, 3, 4, 5, 6].as_ref());
+        let mut buf = buf.freeze();
+
+        // The padding has not yet been received.
+        assert_eq!(
+            buf.read_variable_bytes(None),
+            Err(Error::Incomplete { needed: 2 })
+        );
+    }
+
+    #[test]
+    fn test_variable_array_incomplete() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(3); // Len=3
+        buf.put_u32(1);
+        buf.put_u32(2);
+        let mut buf = buf.freeze();
+
+        assert_eq!(
+            buf.read_variable_array::<TestStruct>(None),
+            Err(Error::Incomplete { needed: 4 })
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! ONC RPC record marking ([RFC 5531 section 11]) for XDR messages sent
+//! over a stream transport such as TCP.
+//!
+//! Each record is sent as one or more fragments, each prefixed by a 4-byte
+//! header containing the fragment length and a flag marking the last
+//! fragment of the record.
+//!
+//! [RFC 5531 section 11]: https://tools.ietf.org/html/rfc5531#section-11
+
+use super::{Encode, Error};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// The bit in a fragment header set on the last fragment of a record.
+pub const LAST_FRAGMENT: u32 = 1 << 31;
+
+/// The largest fragment length that can be described by a fragment
+/// header.
+pub const MAX_FRAGMENT_LEN: usize = (LAST_FRAGMENT - 1) as usize;
+
+const HEADER_LEN: usize = 4;
+
+/// Removes a complete record from the front of `buf`, joining the
+/// payloads of all its fragments.
+///
+/// Records made of a single fragment are returned without copying.
+///
+/// If `buf` does not yet hold the complete record,
+/// [`Error::Incomplete`] is returned and `buf` is left untouched, so
+/// the call can be retried once more data has been received. Records
+/// with a total payload larger than `max` return
+/// [`Error::InvalidLength`].
+pub fn read_record(buf: &mut BytesMut, max: Option<usize>) -> Result<Bytes, Error> {
+    // Find the extent of the record, and the number of fragments in
+    // it, before consuming anything.
+    let mut offset = 0;
+    let mut payload = 0;
+    let mut fragments = 0;
+    loop {
+        if buf.len() < offset + HEADER_LEN {
+            return Err(Error::Incomplete {
+                needed: offset + HEADER_LEN - buf.len(),
+            });
+        }
+
+        let header = (&buf[offset..offset + HEADER_LEN]).get_u32();
+        let len = (header & !LAST_FRAGMENT) as usize;
+
+        payload += len;
+        if let Some(limit) = max {
+            if payload > limit {
+                return Err(Error::InvalidLength);
+            }
+        }
+
+        offset += HEADER_LEN + len;
+        fragments += 1;
+        if buf.len() < offset {
+            return Err(Error::Incomplete {
+                needed: offset - buf.len(),
+            });
+        }
+
+        if header & LAST_FRAGMENT != 0 {
+            break;
+        }
+    }
+
+    let mut record = buf.split_to(offset);
+    if fragments == 1 {
+        record.advance(HEADER_LEN);
+        return Ok(record.freeze());
+    }
+
+    // Join the fragment payloads into one contiguous buffer.
+    let mut out = BytesMut::with_capacity(payload);
+    while record.has_remaining() {
+        let len = (record.get_u32() & !LAST_FRAGMENT) as usize;
+        out.put_slice(&record[..len]);
+        record.advance(len);
+    }
+
+    Ok(out.freeze())
+}
+
+/// Writes `msg` to `buf` as a single record, split into fragments of
+/// at most `max_fragment` bytes.
+///
+/// An empty `msg` is written as a single, empty fragment.
+pub fn write_record<B: BufMut>(buf: &mut B, msg: &[u8], max_fragment: usize) -> Result<(), Error> {
+    if max_fragment == 0 || max_fragment > MAX_FRAGMENT_LEN {
+        return Err(Error::InvalidLength);
+    }
+
+    let mut chunks = msg.chunks(max_fragment).peekable();
+    if chunks.peek().is_none() {
+        buf.put_u32(LAST_FRAGMENT);
+        return Ok(());
+    }
+
+    while let Some(chunk) = chunks.next() {
+        let mut header = chunk.len() as u32;
+        if chunks.peek().is_none() {
+            header |= LAST_FRAGMENT;
+        }
+
+        buf.put_u32(header);
+        buf.put_slice(chunk);
+    }
+
+    Ok(())
+}
+
+/// Encodes `v` directly into `buf` as a single-fragment record.
+///
+/// If `v` fails to encode, or is too large to fit in a single
+/// fragment, an error is returned and `buf` is left unchanged.
+pub fn encode_record<T: Encode>(v: &T, buf: &mut BytesMut) -> Result<(), Error> {
+    let start = buf.len();
+
+    // Reserve space for the header, and fill it in once the length of
+    // the encoded message is known.
+    buf.put_u32(0);
+    let len = v
+        .encode(buf)
+        .and_then(|_| match buf.len() - start - HEADER_LEN {
+            len if len > MAX_FRAGMENT_LEN => Err(Error::InvalidLength),
+            len => Ok(len),
+        });
+
+    match len {
+        Ok(len) => {
+            let header = LAST_FRAGMENT | len as u32;
+            buf[start..start + HEADER_LEN].copy_from_slice(&header.to_be_bytes());
+            Ok(())
+        }
+        Err(e) => {
+            buf.truncate(start);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_single_fragment() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(LAST_FRAGMENT | 4);
+        buf.put_u32(42);
+        buf.put_u32(123); // Start of the next record
+
+        let got = read_record(&mut buf, None).unwrap();
+        assert_eq!(got.as_ref(), &[0, 0, 0, 42]);
+        assert_eq!(buf.as_ref(), &[0, 0, 0, 123]);
+    }
+
+    #[test]
+    fn test_read_multiple_fragments() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(2);
+        buf.put_slice(&[1, 2]);
+        buf.put_u32(0);
+        buf.put_u32(LAST_FRAGMENT | 3);
+        buf.put_slice(&[3, 4, 5]);
+
+        let got = read_record(&mut buf, None).unwrap();
+        assert_eq!(got.as_ref(), &[1, 2, 3, 4, 5]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_read_incomplete() {
+        let mut buf = BytesMut::new();
+        buf.put_u16(0);
+        assert_eq!(
+            read_record(&mut buf, None),
+            Err(Error::Incomplete { needed: 2 })
+        );
+
+        let mut buf = BytesMut::new();
+        buf.put_u32(2);
+        buf.put_slice(&[1, 2]);
+        buf.put_u32(LAST_FRAGMENT | 3);
+        buf.put_slice(&[3]);
+        assert_eq!(
+            read_record(&mut buf, None),
+            Err(Error::Incomplete { needed: 2 })
+        );
+
+        // The buffer is untouched, and the read succeeds once the rest
+        // of the record arrives.
+        assert_eq!(buf.len(), 11);
+        buf.put_slice(&[4, 5]);
+        let got = read_record(&mut buf, None).unwrap();
+        assert_eq!(got.as_ref(), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_read_exceeds_max() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(LAST_FRAGMENT | 8);
+
+        assert_eq!(read_record(&mut buf, Some(4)), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let mut buf = BytesMut::new();
+        write_record(&mut buf, &[1, 2, 3, 4, 5], 2).unwrap();
+
+        assert_eq!(buf.len(), 3 * HEADER_LEN + 5);
+        assert_eq!(&buf[..HEADER_LEN], &[0, 0, 0, 2]);
+
+        let got = read_record(&mut buf, None).unwrap();
+        assert_eq!(got.as_ref(), &[1, 2, 3, 4, 5]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let mut buf = BytesMut::new();
+        write_record(&mut buf, &[], MAX_FRAGMENT_LEN).unwrap();
+
+        assert_eq!(buf.as_ref(), &[0x80, 0, 0, 0]);
+        assert_eq!(read_record(&mut buf, None).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_write_invalid_fragment_size() {
+        let mut buf = BytesMut::new();
+        assert_eq!(write_record(&mut buf, &[1], 0), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_encode_record() {
+        let mut buf = BytesMut::new();
+        buf.put_u8(1); // Existing data
+
+        encode_record(&42_u32, &mut buf).unwrap();
+        assert_eq!(buf.as_ref(), &[1, 0x80, 0, 0, 4, 0, 0, 0, 42]);
+
+        buf.advance(1);
+        assert_eq!(
+            read_record(&mut buf, None).unwrap().as_ref(),
+            &[0, 0, 0, 42]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Serde support for the runtime types, and the helpers used by generated
+//! code to serialise opaque data as a string.
+
+use super::Quadruple;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// Serialises opaque data as a padded base64 string, for use with
+/// `#[serde(with = "opaque_base64")]`.
+pub mod opaque_base64 {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    pub fn serialize<T: AsRef<[u8]>, S: Serializer>(v: &T, s: S) -> Result<S::Ok, S::Error> {
+        let v = v.as_ref();
+        let mut out = String::with_capacity(v.len().div_ceil(3) * 4);
+        for chunk in v.chunks(3) {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |acc, (i, b)| acc | u32::from(*b) << (16 - 8 * i));
+
+            // A chunk of n bytes is written as n + 1 characters, padded to
+            // four.
+            for i in 0..4 {
+                match i <= chunk.len() {
+                    true => out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char),
+                    false => out.push('='),
+                }
+            }
+        }
+        s.serialize_str(&out)
+    }
+
+    pub fn deserialize<'de, T: From<Vec<u8>>, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let s = String::deserialize(d)?;
+        let s = s.trim_end_matches('=');
+
+        let mut out = Vec::with_capacity(s.len() * 3 / 4);
+        let mut acc = 0u32;
+        let mut bits = 0;
+        for c in s.bytes() {
+            let v = ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .ok_or_else(|| D::Error::custom("invalid base64 character"))?;
+
+            acc = (acc << 6) | v as u32;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                out.push((acc >> bits) as u8);
+                acc &= (1 << bits) - 1;
+            }
+        }
+
+        Ok(T::from(out))
+    }
+}
+
+/// Serialises opaque data as a lowercase hex string, for use with
+/// `#[serde(with = "opaque_hex")]`.
+pub mod opaque_hex {
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: AsRef<[u8]>, S: Serializer>(v: &T, s: S) -> Result<S::Ok, S::Error> {
+        let out = v
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        s.serialize_str(&out)
+    }
+
+    pub fn deserialize<'de, T: From<Vec<u8>>, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let s = String::deserialize(d)?;
+        if s.len() % 2 != 0 {
+            return Err(D::Error::custom("odd length hex string"));
+        }
+
+        s.as_bytes()
+            .chunks(2)
+            .map(|c| {
+                core::str::from_utf8(c)
+                    .ok()
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    .ok_or_else(|| D::Error::custom("invalid hex character"))
+            })
+            .collect::<Result<Vec<u8>, _>>()
+            .map(T::from)
+    }
+}
+
+// A quadruple is always serialised as a hex string, whichever format the
+// generated code uses for opaque data.
+impl serde::Serialize for Quadruple {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        opaque_hex::serialize(&self.0, s)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Quadruple {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let v: Vec<u8> = opaque_hex::deserialize(d)?;
+        <[u8; 16]>::try_from(v.as_slice())
+            .map(Quadruple)
+            .map_err(|_| serde::de::Error::invalid_length(v.len(), &"16 bytes"))
+    }
+}
@@ -0,0 +1,98 @@
+    /// The runtime for the generated ONC RPC servers.
+    ///
+    /// Each program version in the spec has a generated service trait (such as
+    /// `NFS_V3Service`) with a method per procedure, and a server wrapping an
+    /// implementation of it (such as `NFS_V3Server`) that implements
+    /// [`Dispatch`].
+    ///
+    /// Pass each call message received to [`handle()`], and send the reply it
+    /// returns.
+    pub mod server {
+        use super::{
+            accept_stat, auth_flavor, msg_type, reject_stat, reply_stat, rpc_msg, rpc_msg_body,
+            Error, RPC_VERSION,
+        };
+        use fastxdr::bytes::{BufMut, Bytes, BytesMut};
+        use std::convert::TryFrom;
+        use std::future::Future;
+
+        /// `Dispatch` decodes the arguments of a call to a single version of an
+        /// RPC program, and encodes the result of the procedure.
+        pub trait Dispatch {
+            const PROGRAM: u32;
+            const VERSION: u32;
+
+            /// Calls `procedure` with the encoded `args`, writing the encoded
+            /// result to `buf`.
+            ///
+            /// Returns `PROC_UNAVAIL` for unknown procedures, and
+            /// `GARBAGE_ARGS` if the arguments cannot be decoded.
+            fn call(
+                &self,
+                procedure: u32,
+                args: Bytes,
+                buf: &mut BytesMut,
+            ) -> impl Future<Output = Result<(), accept_stat>> + Send;
+        }
+
+        /// Handles a single encoded call message, returning the encoded reply.
+        ///
+        /// Calls for a different program or version than that of `d` are
+        /// rejected with `PROG_UNAVAIL` or `PROG_MISMATCH` replies.
+        ///
+        /// An error is returned if `msg` is not a valid call message, in which
+        /// case no reply should be sent.
+        pub async fn handle<D: Dispatch>(d: &D, mut msg: Bytes) -> Result<Bytes, Error> {
+            let call = rpc_msg::try_from(&mut msg)?;
+            let body = match call.body {
+                rpc_msg_body::CALL(b) => b,
+                rpc_msg_body::REPLY(_) => {
+                    return Err(Error::Unknown(format!(
+                        "received reply message with xid {}, expected a call",
+                        call.xid
+                    )))
+                }
+            };
+
+            let mut buf = BytesMut::new();
+            buf.put_u32(call.xid);
+            buf.put_u32(msg_type::REPLY as u32);
+
+            if body.rpcvers != RPC_VERSION {
+                buf.put_u32(reply_stat::MSG_DENIED as u32);
+                buf.put_u32(reject_stat::RPC_MISMATCH as u32);
+                buf.put_u32(RPC_VERSION);
+                buf.put_u32(RPC_VERSION);
+                return Ok(buf.freeze());
+            }
+
+            // Accepted replies carry an AUTH_NONE verifier.
+            buf.put_u32(reply_stat::MSG_ACCEPTED as u32);
+            buf.put_u32(auth_flavor::AUTH_NONE as u32);
+            buf.put_u32(0);
+
+            let stat = if body.prog != D::PROGRAM {
+                accept_stat::PROG_UNAVAIL
+            } else if body.vers != D::VERSION {
+                buf.put_u32(accept_stat::PROG_MISMATCH as u32);
+                buf.put_u32(D::VERSION);
+                buf.put_u32(D::VERSION);
+                return Ok(buf.freeze());
+            } else {
+                // Write the result after the status, discarding anything
+                // written if the call fails.
+                let header = buf.len();
+                buf.put_u32(accept_stat::SUCCESS as u32);
+                match d.call(body.proc, msg, &mut buf).await {
+                    Ok(()) => return Ok(buf.freeze()),
+                    Err(stat) => {
+                        buf.truncate(header);
+                        stat
+                    }
+                }
+            };
+
+            buf.put_u32(stat as u32);
+            Ok(buf.freeze())
+        }
+    }